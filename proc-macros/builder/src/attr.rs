@@ -0,0 +1,343 @@
+//! Parsing of the inert `#[builder(...)]` attributes.
+
+use proc_macro2::Span;
+use syn::meta::ParseNestedMeta;
+use syn::spanned::Spanned;
+use syn::{Attribute, Expr, Ident, LitBool, LitStr, Path, Token, Type};
+
+use crate::diagnostic::{self, key, Errors, Key};
+
+/// Options given through `#[builder(...)]` on the struct itself.
+#[derive(Default)]
+pub struct StructOpts {
+    pub typestate: bool,
+    pub pattern: Option<Pattern>,
+    /// Unset fields fall back to the struct's own `Default` impl.
+    pub default: bool,
+    /// `fn(&T) -> Result<(), E>` run on the assembled struct.
+    pub validate: Option<Path>,
+    /// The `E` returned by `validate`, `String` unless given.
+    pub validation_error: Option<Type>,
+    pub build_fn: BuildFnOpts,
+    /// Derives forwarded to the builder type.
+    pub derive: Vec<Path>,
+    /// Defaults for every field's setter, only `into` and `strip_option` are allowed.
+    pub setter: SetterOpts,
+    /// Only use `core` and `alloc` in the generated code.
+    pub no_std: bool,
+    /// Where `derive_builder` is reachable from, for derives re-exported by another crate.
+    pub krate: Option<Path>,
+}
+
+impl StructOpts {
+    const KEYS: &'static [Key] = &[
+        key("typestate", "typestate"),
+        key("pattern", "pattern = \"...\""),
+        key("default", "default"),
+        key("validate", "validate = \"...\""),
+        key("validation_error", "validation_error = \"...\""),
+        key("build_fn", "build_fn(...)"),
+        key("derive", "derive(...)"),
+        key("setter", "setter(...)"),
+        key("no_std", "no_std"),
+        key("crate", "crate = \"...\""),
+    ];
+
+    /// Parses the struct's options, recording every problem in `errors` and skipping past it.
+    pub fn from_attrs(attrs: &[Attribute], errors: &mut Errors) -> Self {
+        let mut opts = Self::default();
+        let mut pattern_lit = None;
+
+        for attr in builder_attrs(attrs) {
+            let result = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("typestate") {
+                    opts.typestate = true;
+                    Ok(())
+                } else if meta.path.is_ident("pattern") {
+                    let pattern: LitStr = meta.value()?.parse()?;
+                    opts.pattern = match pattern.value().as_str() {
+                        "mutable" => Some(Pattern::Mutable),
+                        "owned" => Some(Pattern::Owned),
+                        "immutable" => Some(Pattern::Immutable),
+                        _ => {
+                            errors.push(syn::Error::new_spanned(&pattern, "expected `owned`, `mutable` or `immutable`"));
+                            None
+                        }
+                    };
+                    pattern_lit = Some(pattern);
+                    Ok(())
+                } else if meta.path.is_ident("default") {
+                    opts.default = true;
+                    Ok(())
+                } else if meta.path.is_ident("validate") {
+                    let path: LitStr = meta.value()?.parse()?;
+                    opts.validate = errors.ok(path.parse());
+                    Ok(())
+                } else if meta.path.is_ident("validation_error") {
+                    let ty: LitStr = meta.value()?.parse()?;
+                    opts.validation_error = errors.ok(ty.parse());
+                    Ok(())
+                } else if meta.path.is_ident("derive") {
+                    meta.parse_nested_meta(|meta| {
+                        opts.derive.push(meta.path);
+                        Ok(())
+                    })
+                } else if meta.path.is_ident("build_fn") {
+                    opts.build_fn.parse(&meta, errors)
+                } else if meta.path.is_ident("setter") {
+                    opts.setter.parse(&meta, errors)?;
+                    if let Some(name) = opts.setter.name.take() {
+                        errors.push(syn::Error::new_spanned(name, "`setter(name = \"...\")` is only allowed on fields"));
+                    }
+                    if opts.setter.skip {
+                        errors.push(meta.error("`setter(skip)` is only allowed on fields"));
+                    }
+                    Ok(())
+                } else if meta.path.is_ident("no_std") {
+                    opts.no_std = true;
+                    Ok(())
+                } else if meta.path.is_ident("crate") {
+                    let path: LitStr = meta.value()?.parse()?;
+                    opts.krate = errors.ok(path.parse());
+                    Ok(())
+                } else {
+                    diagnostic::unknown_key(errors, &meta, Some(&attr.meta), Self::KEYS)
+                }
+            });
+            errors.ok(result);
+        }
+
+        if let (true, Some(pattern)) = (opts.typestate, &pattern_lit) {
+            if opts.pattern != Some(Pattern::Owned) {
+                errors.push(syn::Error::new_spanned(pattern, "typestate builders are always owned"));
+            }
+        }
+        if let (false, Some(ty)) = (opts.validates(), &opts.validation_error) {
+            errors.push(syn::Error::new_spanned(
+                ty,
+                "`validation_error` requires `builder(validate = \"...\")` or `builder(build_fn(async_validate = \"...\"))`",
+            ));
+        }
+
+        opts
+    }
+
+    /// Whether `build` runs a validation function, synchronous or not.
+    pub fn validates(&self) -> bool {
+        self.validate.is_some() || self.build_fn.async_validate.is_some()
+    }
+}
+
+/// `build_fn(...)` options.
+#[derive(Default)]
+pub struct BuildFnOpts {
+    pub name: Option<Ident>,
+    /// `async fn(&T) -> Result<(), E>` awaited after `validate`, which makes `build` async.
+    pub async_validate: Option<Path>,
+}
+
+impl BuildFnOpts {
+    const KEYS: &'static [Key] = &[key("name", "name = \"...\""), key("async_validate", "async_validate = \"...\"")];
+
+    fn parse(&mut self, meta: &ParseNestedMeta, errors: &mut Errors) -> syn::Result<()> {
+        meta.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                let name: LitStr = meta.value()?.parse()?;
+                self.name = errors.ok(diagnostic::parse_ident(&name));
+                Ok(())
+            } else if meta.path.is_ident("async_validate") {
+                let path: LitStr = meta.value()?.parse()?;
+                self.async_validate = errors.ok(path.parse());
+                Ok(())
+            } else {
+                diagnostic::unknown_key(errors, &meta, None, Self::KEYS)
+            }
+        })
+    }
+}
+
+/// How setters and `build` take the builder.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Pattern {
+    /// `&mut self`, `build` clones the fields.
+    Mutable,
+    /// `self`, `build` moves the fields out.
+    Owned,
+    /// `&self`, setters return a modified clone of the builder.
+    Immutable,
+}
+
+/// Options given through `#[builder(...)]` on a single field.
+#[derive(Default)]
+pub struct FieldOpts {
+    pub each: Option<EachOpts>,
+    pub default: Option<FieldDefault>,
+    pub setter: SetterOpts,
+    /// Also generate a `try_<setter>` accepting `impl TryInto<T>`.
+    pub try_setter: bool,
+    /// The field's type derives `Builder` too, and is built through its builder.
+    pub sub_builder: bool,
+    /// Where a bare `default` was given, which is pointless on `Option` fields.
+    pub default_span: Option<Span>,
+}
+
+/// `each = "name"` or `each(name = "...", item = "...")`.
+pub struct EachOpts {
+    pub name: Ident,
+    /// The item passed to `Extend`, required for collections other than the std ones.
+    pub item: Option<Type>,
+}
+
+impl EachOpts {
+    const KEYS: &'static [Key] = &[key("name", "name = \"...\""), key("item", "item = \"...\"")];
+
+    /// Returns `None` if the options are unusable, after recording why in `errors`.
+    fn parse(meta: &ParseNestedMeta, errors: &mut Errors) -> syn::Result<Option<Self>> {
+        if meta.input.peek(Token![=]) {
+            let name: LitStr = meta.value()?.parse()?;
+            return Ok(errors.ok(diagnostic::parse_ident(&name)).map(|name| Self { name, item: None }));
+        }
+
+        let mut name = None;
+        let mut valid = true;
+        let mut item = None;
+        meta.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                let lit: LitStr = meta.value()?.parse()?;
+                name = errors.ok(diagnostic::parse_ident(&lit));
+                valid &= name.is_some();
+                Ok(())
+            } else if meta.path.is_ident("item") {
+                let lit: LitStr = meta.value()?.parse()?;
+                item = errors.ok(lit.parse());
+                valid &= item.is_some();
+                Ok(())
+            } else {
+                diagnostic::unknown_key(errors, &meta, None, Self::KEYS)
+            }
+        })?;
+
+        match name {
+            Some(name) if valid => Ok(Some(Self { name, item })),
+            Some(_) => Ok(None),
+            None if valid => Err(meta.error("expected `each(name = \"...\")`")),
+            None => Ok(None),
+        }
+    }
+}
+
+/// `setter(...)` options, on a field or as defaults for all fields on the struct.
+#[derive(Default)]
+pub struct SetterOpts {
+    /// Accept `impl Into<T>` instead of `T`.
+    pub into: Option<bool>,
+    /// Whether the setter of an `Option<T>` field takes `T` instead of `Option<T>`.
+    pub strip_option: Option<bool>,
+    pub name: Option<Ident>,
+    /// Don't generate a setter, the field is always built from its default.
+    pub skip: bool,
+}
+
+impl SetterOpts {
+    const KEYS: &'static [Key] = &[
+        key("into", "into"),
+        key("strip_option", "strip_option"),
+        key("name", "name = \"...\""),
+        key("skip", "skip"),
+    ];
+
+    fn parse(&mut self, meta: &ParseNestedMeta, errors: &mut Errors) -> syn::Result<()> {
+        meta.parse_nested_meta(|meta| {
+            if meta.path.is_ident("into") {
+                self.into = Some(parse_flag(&meta)?);
+                Ok(())
+            } else if meta.path.is_ident("strip_option") {
+                self.strip_option = Some(parse_flag(&meta)?);
+                Ok(())
+            } else if meta.path.is_ident("name") {
+                let name: LitStr = meta.value()?.parse()?;
+                self.name = errors.ok(diagnostic::parse_ident(&name));
+                Ok(())
+            } else if meta.path.is_ident("skip") {
+                self.skip = true;
+                Ok(())
+            } else {
+                diagnostic::unknown_key(errors, &meta, None, Self::KEYS)
+            }
+        })
+    }
+}
+
+/// The value an unset field falls back to.
+pub enum FieldDefault {
+    /// `#[builder(default)]`
+    Trait,
+    /// `#[builder(default = "expr")]`, evaluated when `build` is called.
+    Expr(Expr),
+    /// The field's value in the struct's `Default` impl, see [`StructOpts::default`].
+    Struct,
+}
+
+impl FieldOpts {
+    const KEYS: &'static [Key] = &[
+        key("each", "each = \"...\""),
+        key("default", "default"),
+        key("setter", "setter(...)"),
+        key("try_setter", "try_setter"),
+        key("sub_builder", "sub_builder"),
+    ];
+
+    /// Parses a field's options, recording every problem in `errors` and skipping past it.
+    pub fn from_attrs(attrs: &[Attribute], errors: &mut Errors) -> Self {
+        let mut opts = Self::default();
+
+        for attr in builder_attrs(attrs) {
+            let result = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("each") {
+                    opts.each = EachOpts::parse(&meta, errors)?;
+                    Ok(())
+                } else if meta.path.is_ident("default") {
+                    opts.default_span = (!meta.input.peek(Token![=])).then(|| meta.path.span());
+                    opts.default = parse_default(&meta, errors)?;
+                    Ok(())
+                } else if meta.path.is_ident("setter") {
+                    opts.setter.parse(&meta, errors)
+                } else if meta.path.is_ident("try_setter") {
+                    opts.try_setter = true;
+                    Ok(())
+                } else if meta.path.is_ident("sub_builder") {
+                    opts.sub_builder = true;
+                    Ok(())
+                } else {
+                    diagnostic::unknown_key(errors, &meta, Some(&attr.meta), Self::KEYS)
+                }
+            });
+            errors.ok(result);
+        }
+
+        opts
+    }
+}
+
+/// Parses a bare `flag` as `true`, or `flag = <bool>`.
+fn parse_flag(meta: &ParseNestedMeta) -> syn::Result<bool> {
+    if meta.input.peek(Token![=]) {
+        let value: LitBool = meta.value()?.parse()?;
+        Ok(value.value)
+    } else {
+        Ok(true)
+    }
+}
+
+fn parse_default(meta: &ParseNestedMeta, errors: &mut Errors) -> syn::Result<Option<FieldDefault>> {
+    if meta.input.peek(Token![=]) {
+        let expr: LitStr = meta.value()?.parse()?;
+        Ok(errors.ok(expr.parse()).map(FieldDefault::Expr))
+    } else {
+        Ok(Some(FieldDefault::Trait))
+    }
+}
+
+fn builder_attrs(attrs: &[Attribute]) -> impl Iterator<Item=&Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("builder"))
+}
@@ -0,0 +1,121 @@
+//! Collecting errors so that every misuse of `#[builder(...)]` is reported at once.
+
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::meta::ParseNestedMeta;
+use syn::{token, Expr, Ident, LitStr, Token};
+
+/// All errors found so far, reported together by [`Errors::finish`].
+#[derive(Default)]
+pub struct Errors(Option<syn::Error>);
+
+impl Errors {
+    pub fn push(&mut self, error: syn::Error) {
+        match &mut self.0 {
+            Some(errors) => errors.combine(error),
+            None => self.0 = Some(error),
+        }
+    }
+
+    /// Records the error of `result`, if any, and returns its value otherwise.
+    pub fn ok<T>(&mut self, result: syn::Result<T>) -> Option<T> {
+        result.map_err(|error| self.push(error)).ok()
+    }
+
+    pub fn finish(self) -> syn::Result<()> {
+        self.0.map_or(Ok(()), Err)
+    }
+}
+
+/// A key accepted inside some `(...)` list, with how it's written.
+pub struct Key {
+    pub name: &'static str,
+    pub syntax: &'static str,
+}
+
+pub const fn key(name: &'static str, syntax: &'static str) -> Key {
+    Key { name, syntax }
+}
+
+/// Reports `meta` as not being one of `keys` and skips its value, so parsing can go on.
+///
+/// `attr` is the whole `builder(...)` for keys given directly in it, the error is then reported
+/// on `attr`, naming the attribute that was probably meant, or all of them.
+pub fn unknown_key(errors: &mut Errors, meta: &ParseNestedMeta, attr: Option<&dyn ToTokens>, keys: &[Key]) -> syn::Result<()> {
+    let unknown = meta.path.to_token_stream().to_string();
+    let suggestion = keys.iter().find(|key| is_similar(&unknown, key.name));
+
+    match attr {
+        Some(attr) => {
+            let expected = match suggestion {
+                Some(key) => format!("expected `builder({})`", key.syntax),
+                None => expected(keys, |key| format!("`builder({})`", key.syntax)),
+            };
+            errors.push(syn::Error::new_spanned(attr, expected));
+        }
+        None => errors.push(match suggestion {
+            Some(key) => meta.error(format!("unknown option `{}`, did you mean `{}`?", unknown, key.name)),
+            None => meta.error(expected(keys, |key| format!("`{}`", key.syntax))),
+        }),
+    }
+
+    skip_value(meta)
+}
+
+fn expected(keys: &[Key], syntax: impl Fn(&Key) -> String) -> String {
+    let mut keys: Vec<_> = keys.iter().map(syntax).collect();
+    match keys.pop() {
+        Some(last) if keys.is_empty() => format!("expected {}", last),
+        Some(last) => format!("expected one of {} or {}", keys.join(", "), last),
+        None => "unexpected option".to_owned(),
+    }
+}
+
+/// Consumes `= value` or `(...)` after a key.
+fn skip_value(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<Expr>()?;
+    } else if meta.input.peek(token::Paren) {
+        let _content;
+        syn::parenthesized!(_content in meta.input);
+    }
+    Ok(())
+}
+
+/// Parses the contents of `lit` as the name of a generated method.
+pub fn parse_ident(lit: &LitStr) -> syn::Result<Ident> {
+    let value = lit.value();
+    let valid = value.parse::<TokenStream>().ok().and_then(|tokens| syn::parse2::<Ident>(tokens).ok());
+
+    match valid {
+        Some(mut ident) => {
+            ident.set_span(lit.span());
+            Ok(ident)
+        }
+        None => Err(syn::Error::new(lit.span(), format!("`{}` is not a valid identifier", value))),
+    }
+}
+
+/// Whether a mistyped `unknown` probably meant `known`.
+fn is_similar(unknown: &str, known: &str) -> bool {
+    let distance = edit_distance(unknown, known);
+    distance <= (known.len() / 3).max(1) && distance < unknown.len()
+}
+
+/// The Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substitute = diagonal + usize::from(a != *b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
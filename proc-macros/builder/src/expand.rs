@@ -0,0 +1,1103 @@
+//! Code generation for `#[derive(Builder)]`.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{parse_quote, Data, DeriveInput, Fields, GenericParam, Ident, Type};
+
+use crate::attr::{FieldDefault, Pattern, StructOpts};
+use crate::diagnostic::Errors;
+use crate::field::{BuilderField, FieldKind};
+use crate::ty::{self, EachItem};
+
+pub fn derive(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let mut errors = Errors::default();
+    let opts = StructOpts::from_attrs(&input.attrs, &mut errors);
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    // Nothing in the expansion names the crate, this only reports a wrong path early.
+    let crate_check = opts.krate.as_ref().map(|krate| quote! {
+        const _: () = {
+            use #krate as _;
+        };
+    });
+
+    match &input.data {
+        Data::Struct(data) => {
+            let builder = Builder::new(input, &opts, format_ident!("{}Builder", ident), quote!(#ident), &data.fields, false, &mut errors)?;
+            errors.finish()?;
+            let expanded = builder.expand();
+            let new = builder.new_fn(&format_ident!("builder"), false);
+            let from = builder.impl_from();
+            let to_builder = builder.to_builder_fn();
+
+            Ok(quote! {
+                #crate_check
+                #expanded
+                #from
+
+                impl #impl_generics #ident #ty_generics #where_clause {
+                    #new
+                    #to_builder
+                }
+            })
+        }
+        Data::Enum(data) => {
+            if opts.default {
+                errors.push(syn::Error::new_spanned(ident, "`builder(default)` requires a struct, use field defaults instead"));
+            }
+
+            // `<Enum>Builder` only groups the constructors of the variant builders.
+            let vis = &input.vis;
+            let namespace = format_ident!("{}Builder", ident);
+            let mut expanded = Vec::new();
+            let mut constructors = Vec::new();
+            for variant in &data.variants {
+                if !matches!(variant.fields, Fields::Named(_)) {
+                    continue;
+                }
+
+                let variant_ident = &variant.ident;
+                let builder_ident = format_ident!("{}{}Builder", ident, variant_ident);
+                let path = quote!(#ident::#variant_ident);
+                let builder = Builder::new(input, &opts, builder_ident, path, &variant.fields, true, &mut errors)?;
+
+                expanded.push(builder.expand());
+                constructors.push(builder.new_fn(&snake_case(variant_ident), true));
+            }
+            errors.finish()?;
+
+            Ok(quote! {
+                #crate_check
+                #vis struct #namespace;
+
+                impl #namespace {
+                    #(#constructors)*
+                }
+
+                #(#expanded)*
+            })
+        }
+        Data::Union(_) => Err(syn::Error::new_spanned(ident, "`Builder` can't be derived for unions")),
+    }
+}
+
+struct Builder<'a> {
+    input: &'a DeriveInput,
+    ident: Ident,
+    /// The struct or enum variant `build` constructs.
+    path: TokenStream,
+    opts: &'a StructOpts,
+    fields: Vec<BuilderField<'a>>,
+    /// Built from an enum variant, whose fields may not use all of the enum's parameters.
+    variant: bool,
+}
+
+impl<'a> Builder<'a> {
+    fn new(
+        input: &'a DeriveInput,
+        opts: &'a StructOpts,
+        ident: Ident,
+        path: TokenStream,
+        fields: &'a Fields,
+        variant: bool,
+        errors: &mut Errors,
+    ) -> syn::Result<Self> {
+        let named = match fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(&input.ident, "`Builder` requires named fields")),
+        };
+        let fields = named
+            .iter()
+            .map(|field| BuilderField::new(field, opts, errors))
+            .collect();
+
+        let builder = Self { input, ident, path, opts, fields, variant };
+        builder.check_method_names(errors);
+        Ok(builder)
+    }
+
+    /// Reports setters that would end up with the same name as another method of the builder.
+    fn check_method_names(&self, errors: &mut Errors) {
+        let mut methods = vec![
+            (self.build_name(), "the builder's build method".to_owned()),
+            (format_ident!("merge"), "the builder's `merge` method".to_owned()),
+        ];
+
+        for field in &self.fields {
+            let mut names = Vec::new();
+            if field.has_field_setter() {
+                names.push((field.setter.name.clone(), format!("the setter of `{}`", field.ident)));
+            }
+            if field.try_setter {
+                let name = &field.setter.name;
+                let try_name = format_ident!("try_{}", name.unraw(), span = name.span());
+                names.push((try_name, format!("the try setter of `{}`", field.ident)));
+            }
+            match &field.kind {
+                FieldKind::Each { setter, .. } => {
+                    names.push((setter.clone(), format!("the `each` setter of `{}`", field.ident)));
+                }
+                FieldKind::SubBuilder { .. } => {
+                    names.push((sub_builder_name(field), format!("the sub-builder of `{}`", field.ident)));
+                }
+                _ => {}
+            }
+
+            for (name, method) in names {
+                match methods.iter().find(|(other, _)| other.unraw() == name.unraw()) {
+                    Some((_, other)) => errors.push(syn::Error::new(
+                        name.span(),
+                        format!("{} is named `{}`, which is already used by {}", method, name.unraw(), other),
+                    )),
+                    None => methods.push((name, method)),
+                }
+            }
+        }
+    }
+
+    fn expand(&self) -> TokenStream {
+        if self.opts.typestate {
+            self.typestate()
+        } else {
+            self.runtime_checked()
+        }
+    }
+
+    /// A function `name` returning an empty builder.
+    ///
+    /// It's `generic` over the struct's parameters if it isn't placed in an impl that already is.
+    fn new_fn(&self, name: &Ident, generic: bool) -> TokenStream {
+        let vis = &self.input.vis;
+        let builder_ident = &self.ident;
+        let (impl_generics, ty_generics, where_clause) = self.input.generics.split_for_impl();
+        let init = self.init();
+
+        let (fn_generics, fn_where) = if generic {
+            (Some(impl_generics), where_clause)
+        } else {
+            (None, None)
+        };
+
+        quote! {
+            #vis fn #name #fn_generics () -> #builder_ident #ty_generics #fn_where {
+                #builder_ident {
+                    #(#init,)*
+                }
+            }
+        }
+    }
+
+    /// Setters take the builder as chosen by `pattern`, and `build` reports missing fields at
+    /// runtime.
+    fn runtime_checked(&self) -> TokenStream {
+        let Self { input, ident: builder_ident, path, fields, .. } = self;
+        let vis = &input.vis;
+        let ident = &input.ident;
+        let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+        let generics = &input.generics;
+
+        let storage = self.storage();
+
+        let pattern = self.opts.pattern.unwrap_or(Pattern::Mutable);
+        // `target` is the builder the setter modifies and returns.
+        let (receiver, returned, target, clone_bound, clone_self) = match pattern {
+            Pattern::Mutable => (quote!(&mut self), quote!(&mut Self), quote!(self), None, None),
+            Pattern::Owned => (quote!(mut self), quote!(Self), quote!(self), None, None),
+            Pattern::Immutable => (
+                quote!(&self),
+                quote!(Self),
+                quote!(__builder),
+                Some(quote! { Self: ::core::clone::Clone }),
+                Some(quote! { let mut __builder = ::core::clone::Clone::clone(self); }),
+            ),
+        };
+        let setter_where = clone_bound.as_ref().map(|bound| quote! { where #bound });
+        // Only calls the setter, so an owned `self` doesn't need to be `mut`.
+        let try_receiver = match pattern {
+            Pattern::Owned => quote!(self),
+            _ => receiver.clone(),
+        };
+        let derives = self.derives(pattern == Pattern::Immutable);
+        let merge = self.merge_fn();
+
+        let setters = fields.iter().map(|field| {
+            let field_ident = field.ident;
+            let setter_name = &field.setter.name;
+            let (param_ty, stored) = setter_input(field, field_ident);
+
+            let field_setter = field.has_field_setter().then(|| quote! {
+                pub fn #setter_name(#receiver, #field_ident: #param_ty) -> #returned #setter_where {
+                    #clone_self
+                    #target.#field_ident = #stored;
+                    #target
+                }
+            });
+            let try_setter = try_setter(field, &try_receiver, &returned, clone_bound.as_ref());
+            let each_setter = match &field.kind {
+                FieldKind::Each { setter, item } => {
+                    let (params, item) = each_input(field, item, setter);
+                    Some(quote! {
+                        pub fn #setter(#receiver, #params) -> #returned #setter_where {
+                            #clone_self
+                            ::core::iter::Extend::extend(
+                                #target.#field_ident.get_or_insert_with(::core::default::Default::default),
+                                ::core::iter::once(#item),
+                            );
+                            #target
+                        }
+                    })
+                }
+                FieldKind::SubBuilder { builder, .. } => {
+                    let name = sub_builder_name(field);
+                    let ty = field.ty;
+                    Some(quote! {
+                        pub fn #name(&mut self) -> &mut #builder {
+                            self.#field_ident.get_or_insert_with(<#ty>::builder)
+                        }
+                    })
+                }
+                _ => None,
+            };
+
+            quote! { #field_setter #try_setter #each_setter }
+        });
+
+        let error_ident = format_ident!("{}Error", builder_ident);
+        let error = self.error(&error_ident);
+        let validation_error = self.validation_error();
+        let validate = self.opts.validate.as_ref().map(|validate| quote! {
+            ::core::result::Result::map_err(#validate(&built), #error_ident::Validation)?;
+        });
+        let async_validate = self.opts.build_fn.async_validate.as_ref().map(|validate| quote! {
+            ::core::result::Result::map_err(#validate(&built).await, #error_ident::Validation)?;
+        });
+        let asyncness = async_validate.as_ref().map(|_| quote!(async));
+        let build_name = self.build_name();
+
+        // An owned builder is consumed by `build`, the others are left untouched and clone from it.
+        let owned = pattern == Pattern::Owned;
+        let build_receiver = if owned { quote!(self) } else { quote!(&self) };
+        let struct_default = self.struct_default();
+        let build_bounds = self.build_bounds(!owned);
+        let build_fields = self.checked_build_fields(&error_ident, owned);
+        let build_nested = self.build_nested_fn(&error_ident, validate.as_ref());
+
+        quote! {
+            #derives
+            #vis struct #builder_ident #generics #where_clause {
+                #(#storage,)*
+            }
+
+            impl #impl_generics #builder_ident #ty_generics #where_clause {
+                #(#setters)*
+
+                #merge
+
+                #build_nested
+
+                pub #asyncness fn #build_name(#build_receiver) -> ::core::result::Result<#ident #ty_generics, #error_ident<#validation_error>>
+                #build_bounds
+                {
+                    #struct_default
+                    let built = #path {
+                        #(#build_fields,)*
+                    };
+                    #validate
+                    #async_validate
+                    ::core::result::Result::Ok(built)
+                }
+            }
+
+            #error
+        }
+    }
+
+    /// The fields of a runtime checked `build`, which returns `error_ident` for missing fields.
+    ///
+    /// `owned` builders move their values out, the others clone them.
+    fn checked_build_fields(&self, error_ident: &Ident, owned: bool) -> Vec<TokenStream> {
+        self.fields
+            .iter()
+            .map(|field| {
+                let field_ident = field.ident;
+                let name = field_ident.unraw().to_string();
+                let (storage, take) = match &field.kind {
+                    // Whatever the sub-builder's pattern or build method, it can be built from a reference.
+                    FieldKind::SubBuilder { .. } => {
+                        let storage = if owned { quote! { self.#field_ident } } else { quote! { &self.#field_ident } };
+                        let take = quote! {
+                            match value.__build_nested() {
+                                ::core::result::Result::Ok(value) => value,
+                                ::core::result::Result::Err(error) => {
+                                    return ::core::result::Result::Err(#error_ident::__from_nested(#name, error));
+                                }
+                            }
+                        };
+                        (storage, take)
+                    }
+                    _ if owned => (quote! { self.#field_ident }, quote! { value }),
+                    _ => (quote! { &self.#field_ident }, quote! { ::core::clone::Clone::clone(value) }),
+                };
+                let value = build_value(
+                    field,
+                    storage,
+                    take,
+                    quote! { return ::core::result::Result::Err(#error_ident::MissingField(#name)) },
+                );
+                quote! { #field_ident: #value }
+            })
+            .collect()
+    }
+
+    /// `__build_nested(&self)`, which a parent builder calls to build this one as its
+    /// `sub_builder`. It's the same for every pattern and build method, so the parent doesn't
+    /// need to know them. `async_validate` can't be awaited there and is left to `build`.
+    fn build_nested_fn(&self, error_ident: &Ident, validate: Option<&TokenStream>) -> TokenStream {
+        let vis = &self.input.vis;
+        let ident = &self.input.ident;
+        let path = &self.path;
+        let (_, ty_generics, _) = self.input.generics.split_for_impl();
+        let alloc = self.alloc();
+        let string = quote! { #alloc::string::String };
+        let validation_error = self.validation_error();
+        let struct_default = self.struct_default();
+        let build_fields = self.checked_build_fields(error_ident, false);
+
+        // Like `to_builder`, the bounds are higher-ranked so they're only checked where the
+        // method is called, builders with fields that aren't `Clone` keep compiling.
+        let mut bounds = self.bounds(false);
+        for field in &self.fields {
+            if !matches!(field.kind, FieldKind::SubBuilder { .. }) {
+                let value_ty = field.value_ty();
+                bounds.push(quote! { for<'__nested> #value_ty: ::core::clone::Clone });
+            }
+        }
+        bounds.push(quote! { for<'__nested> #validation_error: ::core::fmt::Display });
+
+        quote! {
+            #[doc(hidden)]
+            #vis fn __build_nested(&self)
+                -> ::core::result::Result<#ident #ty_generics, (::core::option::Option<#string>, ::core::option::Option<#string>)>
+            where
+                #(#bounds,)*
+            {
+                let build = || -> ::core::result::Result<#ident #ty_generics, #error_ident<#validation_error>> {
+                    #struct_default
+                    let built = #path {
+                        #(#build_fields,)*
+                    };
+                    #validate
+                    ::core::result::Result::Ok(built)
+                };
+                build().map_err(#error_ident::__into_nested)
+            }
+        }
+    }
+
+    /// Every required field gets a `const bool` parameter on the builder that flips to `true`
+    /// once the field is set. `build` only exists when all of them are `true`.
+    fn typestate(&self) -> TokenStream {
+        let Self { input, ident: builder_ident, path, fields, .. } = self;
+        let vis = &input.vis;
+        let ident = &input.ident;
+
+        let storage = self.storage();
+
+        let required = fields
+            .iter()
+            .filter(|field| field.is_required())
+            .collect::<Vec<_>>();
+        let markers = required
+            .iter()
+            .map(|field| format_ident!("__{}", field.ident.unraw().to_string().to_uppercase()))
+            .collect::<Vec<_>>();
+
+        let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+        let args = self.generic_args();
+        let mut generics = input.generics.clone();
+        generics.params.extend(markers.iter().map(|marker| -> GenericParam {
+            parse_quote!(const #marker: bool = false)
+        }));
+        let (state_impl_generics, state_ty_generics, _) = generics.split_for_impl();
+
+        let setters = fields.iter().map(|field| {
+            let field_ident = field.ident;
+            let setter_name = &field.setter.name;
+            let (param_ty, stored) = setter_input(field, field_ident);
+
+            let returned = if field.is_required() {
+                let state = required.iter().zip(&markers).map(|(other, marker)| {
+                    if other.ident == field_ident { quote!(true) } else { quote!(#marker) }
+                });
+                quote! { #builder_ident<#(#args,)* #(#state),*> }
+            } else {
+                quote!(Self)
+            };
+
+            let field_setter = if !field.has_field_setter() {
+                None
+            } else if field.is_required() {
+                let moved = fields.iter().filter(|other| other.ident != field_ident).map(|other| {
+                    let other = other.ident;
+                    quote! { #other: self.#other }
+                });
+                let marker = self.marker_ty().map(|_| quote! { __marker: self.__marker, });
+
+                Some(quote! {
+                    pub fn #setter_name(self, #field_ident: #param_ty) -> #returned {
+                        #builder_ident {
+                            #field_ident: #stored,
+                            #(#moved,)*
+                            #marker
+                        }
+                    }
+                })
+            } else {
+                Some(quote! {
+                    pub fn #setter_name(mut self, #field_ident: #param_ty) -> Self {
+                        self.#field_ident = #stored;
+                        self
+                    }
+                })
+            };
+            let try_setter = try_setter(field, &quote!(self), &returned, None);
+            let each_setter = match &field.kind {
+                FieldKind::Each { setter, item } => {
+                    let (params, item) = each_input(field, item, setter);
+                    Some(quote! {
+                        pub fn #setter(mut self, #params) -> Self {
+                            ::core::iter::Extend::extend(
+                                self.#field_ident.get_or_insert_with(::core::default::Default::default),
+                                ::core::iter::once(#item),
+                            );
+                            self
+                        }
+                    })
+                }
+                _ => None,
+            };
+
+            quote! { #field_setter #try_setter #each_setter }
+        });
+
+        let struct_default = self.struct_default();
+        let build_bounds = self.build_bounds(false);
+        let build_fields = fields.iter().map(|field| {
+            let field_ident = field.ident;
+            let value = build_value(
+                field,
+                quote! { self.#field_ident },
+                quote! { value },
+                quote! { ::core::unreachable!() },
+            );
+            quote! { #field_ident: #value }
+        });
+        let filled_ty = self.filled_ty();
+
+        // Nothing can be missing, so the only possible errors are the ones from validation.
+        let build_name = self.build_name();
+        let build = if self.opts.validates() {
+            let validation_error = self.validation_error();
+            let validate = self.opts.validate.as_ref().map(|validate| quote! {
+                #validate(&built)?;
+            });
+            let async_validate = self.opts.build_fn.async_validate.as_ref().map(|validate| quote! {
+                #validate(&built).await?;
+            });
+            let asyncness = async_validate.as_ref().map(|_| quote!(async));
+
+            quote! {
+                pub #asyncness fn #build_name(self) -> ::core::result::Result<#ident #ty_generics, #validation_error>
+                #build_bounds
+                {
+                    #struct_default
+                    let built = #path {
+                        #(#build_fields,)*
+                    };
+                    #validate
+                    #async_validate
+                    ::core::result::Result::Ok(built)
+                }
+            }
+        } else {
+            quote! {
+                pub fn #build_name(self) -> #ident #ty_generics
+                #build_bounds
+                {
+                    #struct_default
+                    #path {
+                        #(#build_fields,)*
+                    }
+                }
+            }
+        };
+
+        let derives = self.derives(false);
+        let merge = self.merge_fn();
+        let not_nested = self.not_nested();
+        let not_nested_marker = self.not_nested_marker();
+
+        quote! {
+            #derives
+            #vis struct #builder_ident #generics #where_clause {
+                #(#storage,)*
+            }
+
+            impl #state_impl_generics #builder_ident #state_ty_generics #where_clause {
+                #(#setters)*
+
+                #merge
+
+                #not_nested
+            }
+
+            #not_nested_marker
+
+            impl #impl_generics #filled_ty #where_clause {
+                #build
+            }
+        }
+    }
+
+    /// A typestate builder can't be filled in place, so it can't be a parent's `sub_builder`.
+    /// It still gets the hidden methods the parent calls, but with a bound that's never met,
+    /// so the parent fails to compile with a message saying why.
+    fn not_nested(&self) -> TokenStream {
+        let vis = &self.input.vis;
+        let ident = &self.input.ident;
+        let (_, ty_generics, _) = self.input.generics.split_for_impl();
+        let alloc = self.alloc();
+        let string = quote! { #alloc::string::String };
+        let marker = format_ident!("__{}Nested", self.ident);
+
+        quote! {
+            #[doc(hidden)]
+            #vis fn __build_nested(&self)
+                -> ::core::result::Result<#ident #ty_generics, (::core::option::Option<#string>, ::core::option::Option<#string>)>
+            where
+                for<'__nested> Self: #marker,
+            {
+                ::core::unreachable!()
+            }
+
+            #[doc(hidden)]
+            #vis fn __from_built(_: #ident #ty_generics) -> Self
+            where
+                for<'__nested> Self: #marker,
+            {
+                ::core::unreachable!()
+            }
+        }
+    }
+
+    /// The trait behind [`Self::not_nested`]'s bound, which no type implements.
+    fn not_nested_marker(&self) -> TokenStream {
+        let vis = &self.input.vis;
+        let marker = format_ident!("__{}Nested", self.ident);
+        let message = format!("`{}` uses `builder(typestate)` and can't be a `sub_builder`", self.ident);
+
+        quote! {
+            #[doc(hidden)]
+            #[diagnostic::on_unimplemented(
+                message = #message,
+                note = "remove `builder(sub_builder)` from the field and set its value as a whole"
+            )]
+            #vis trait #marker {}
+        }
+    }
+
+    /// The `#[derive(...)]` of the builder type, `clone` adds `Clone` unless it's already given.
+    fn derives(&self, clone: bool) -> Option<TokenStream> {
+        let mut derives = self.opts.derive.iter().map(|path| quote! { #path }).collect::<Vec<_>>();
+        let has_clone = self.opts.derive.iter().any(|path| {
+            path.segments.last().is_some_and(|segment| segment.ident == "Clone")
+        });
+        if clone && !has_clone {
+            derives.push(quote! { ::core::clone::Clone });
+        }
+
+        (!derives.is_empty()).then(|| quote! { #[derive(#(#derives),*)] })
+    }
+
+    /// The builder's type once every field is set, `build` is always callable on it.
+    fn filled_ty(&self) -> TokenStream {
+        let builder_ident = &self.ident;
+        let args = self.generic_args();
+        let all_set = self
+            .fields
+            .iter()
+            .filter(|field| self.opts.typestate && field.is_required())
+            .map(|_| quote!(true));
+
+        quote! { #builder_ident<#(#args,)* #(#all_set),*> }
+    }
+
+    /// `impl From<Struct> for StructBuilder`, setting every field to the struct's value.
+    fn impl_from(&self) -> TokenStream {
+        let ident = &self.input.ident;
+        let (impl_generics, ty_generics, where_clause) = self.input.generics.split_for_impl();
+        let filled_ty = self.filled_ty();
+        let fields = self.fields.iter().map(|field| {
+            let field_ident = field.ident;
+            let value = stored_value(field, quote! { value.#field_ident });
+            quote! { #field_ident: #value }
+        });
+
+        // What a parent calls to store a whole value in its `sub_builder` field.
+        let from_built = (!self.opts.typestate).then(|| {
+            let vis = &self.input.vis;
+            quote! {
+                impl #impl_generics #filled_ty #where_clause {
+                    #[doc(hidden)]
+                    #vis fn __from_built(value: #ident #ty_generics) -> Self {
+                        ::core::convert::From::from(value)
+                    }
+                }
+            }
+        });
+
+        quote! {
+            impl #impl_generics ::core::convert::From<#ident #ty_generics> for #filled_ty #where_clause {
+                fn from(value: #ident #ty_generics) -> Self {
+                    Self {
+                        #(#fields,)*
+                    }
+                }
+            }
+
+            #from_built
+        }
+    }
+
+    /// `fn to_builder(&self)`, a builder with every field set to a clone of this value's.
+    fn to_builder_fn(&self) -> TokenStream {
+        let vis = &self.input.vis;
+        let builder_ident = &self.ident;
+        let filled_ty = self.filled_ty();
+        // Bounds on concrete types are checked right away, wrapping them in `for<'__to_builder>`
+        // only checks them where `to_builder` is actually called.
+        let bounds = self
+            .fields
+            .iter()
+            .map(|field| field.ty)
+            .map(|ty| quote! { for<'__to_builder> #ty: ::core::clone::Clone });
+        let fields = self.fields.iter().map(|field| {
+            let field_ident = field.ident;
+            let value = stored_value(field, quote! { ::core::clone::Clone::clone(&self.#field_ident) });
+            quote! { #field_ident: #value }
+        });
+
+        quote! {
+            #vis fn to_builder(&self) -> #filled_ty
+            where
+                #(#bounds,)*
+            {
+                #builder_ident {
+                    #(#fields,)*
+                }
+            }
+        }
+    }
+
+    /// `merge`, which takes every field that is set in `other`. Sub-builders set in both are
+    /// merged in turn.
+    fn merge_fn(&self) -> TokenStream {
+        let merged = self.fields.iter().map(|field| {
+            let field_ident = field.ident;
+            match field.kind {
+                FieldKind::SubBuilder { .. } => quote! {
+                    match (&mut self.#field_ident, other.#field_ident) {
+                        (::core::option::Option::Some(builder), ::core::option::Option::Some(other)) => {
+                            builder.merge(other);
+                        }
+                        (builder, other @ ::core::option::Option::Some(_)) => *builder = other,
+                        (_, ::core::option::Option::None) => {}
+                    }
+                },
+                _ => quote! {
+                    if ::core::option::Option::is_some(&other.#field_ident) {
+                        self.#field_ident = other.#field_ident;
+                    }
+                },
+            }
+        });
+
+        quote! {
+            pub fn merge(&mut self, other: Self) -> &mut Self {
+                #(#merged)*
+                self
+            }
+        }
+    }
+
+    /// The builder's fields: every value is kept in an `Option` until `build` is called.
+    fn storage(&self) -> impl Iterator<Item=TokenStream> + '_ {
+        let fields = self.fields.iter().map(|field| {
+            let field_ident = field.ident;
+            let stored_ty = field.stored_ty();
+            quote! { #field_ident: ::core::option::Option<#stored_ty> }
+        });
+        fields.chain(self.marker_ty().map(|ty| quote! { __marker: #ty }))
+    }
+
+    /// The type of the `__marker` field of variant builders, which uses every parameter of the
+    /// enum in case the variant's fields don't.
+    fn marker_ty(&self) -> Option<TokenStream> {
+        if !self.variant {
+            return None;
+        }
+        let params = self.input.generics.params.iter().filter_map(|param| match param {
+            GenericParam::Lifetime(param) => {
+                let lifetime = &param.lifetime;
+                Some(quote! { &#lifetime () })
+            }
+            GenericParam::Type(param) => {
+                let ident = &param.ident;
+                Some(quote! { #ident })
+            }
+            GenericParam::Const(_) => None,
+        });
+        let params = params.collect::<Vec<_>>();
+
+        (!params.is_empty()).then(|| quote! { ::core::marker::PhantomData<fn() -> (#(#params,)*)> })
+    }
+
+    /// The error returned by a runtime checked `build`.
+    fn error(&self, error_ident: &Ident) -> TokenStream {
+        let vis = &self.input.vis;
+        let alloc = self.alloc();
+        let string = quote! { #alloc::string::String };
+        let nested = self.fields.iter().any(|field| matches!(field.kind, FieldKind::SubBuilder { .. }));
+
+        // Only builders with sub-builders can fail inside another builder.
+        let nested_variants = nested.then(|| quote! {
+            /// A field of a sub-builder was never set, named by its path, e.g. `tls.cert`.
+            MissingNestedField(#string),
+            /// A sub-builder's value was rejected by its `validate` function.
+            InvalidNestedField(#string, #string),
+        });
+        let nested_display = nested.then(|| quote! {
+            #error_ident::MissingNestedField(path) => ::core::write!(f, "missing field `{}`", path),
+            #error_ident::InvalidNestedField(path, error) => ::core::write!(f, "invalid field `{}`: {}", path, error),
+        });
+        let nested_parts = nested.then(|| quote! {
+            #error_ident::MissingNestedField(path) => (::core::option::Option::Some(path), ::core::option::Option::None),
+            #error_ident::InvalidNestedField(path, error) => {
+                (::core::option::Option::Some(path), ::core::option::Option::Some(error))
+            }
+        });
+        let from_nested = nested.then(|| quote! {
+            impl<E> #error_ident<E> {
+                fn __from_nested(
+                    field: &'static str,
+                    (path, error): (::core::option::Option<#string>, ::core::option::Option<#string>),
+                ) -> Self {
+                    let path = match path {
+                        ::core::option::Option::Some(path) => #alloc::format!("{}.{}", field, path),
+                        ::core::option::Option::None => #alloc::borrow::ToOwned::to_owned(field),
+                    };
+                    match error {
+                        ::core::option::Option::Some(error) => #error_ident::InvalidNestedField(path, error),
+                        ::core::option::Option::None => #error_ident::MissingNestedField(path),
+                    }
+                }
+            }
+        });
+
+        quote! {
+            #[derive(::core::fmt::Debug, ::core::clone::Clone, ::core::cmp::PartialEq, ::core::cmp::Eq)]
+            #vis enum #error_ident<E = ::core::convert::Infallible> {
+                /// A required field was never set.
+                MissingField(&'static str),
+                /// The assembled value was rejected by the `validate` function.
+                Validation(E),
+                #nested_variants
+            }
+
+            impl<E: ::core::fmt::Display> ::core::fmt::Display for #error_ident<E> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    match self {
+                        #error_ident::MissingField(field) => ::core::write!(f, "missing field `{}`", field),
+                        #error_ident::Validation(error) => ::core::fmt::Display::fmt(error, f),
+                        #nested_display
+                    }
+                }
+            }
+
+            impl<E: ::core::fmt::Debug + ::core::fmt::Display> ::core::error::Error for #error_ident<E> {}
+
+            impl<E: ::core::fmt::Display> #error_ident<E> {
+                /// The path of the field the error is about, relative to the builder, and what's
+                /// wrong with it unless it's missing. Used when this builder is a `sub_builder`.
+                #[doc(hidden)]
+                #vis fn __into_nested(self) -> (::core::option::Option<#string>, ::core::option::Option<#string>) {
+                    match self {
+                        #error_ident::MissingField(field) => {
+                            (::core::option::Option::Some(#alloc::borrow::ToOwned::to_owned(field)), ::core::option::Option::None)
+                        }
+                        #error_ident::Validation(error) => {
+                            (::core::option::Option::None, ::core::option::Option::Some(#alloc::string::ToString::to_string(&error)))
+                        }
+                        #nested_parts
+                    }
+                }
+            }
+
+            #from_nested
+        }
+    }
+
+    /// The `E` of `validate`'s `Result<(), E>`, or `Infallible` without a `validate` function.
+    fn validation_error(&self) -> TokenStream {
+        match &self.opts.validation_error {
+            Some(ty) => quote! { #ty },
+            None if self.opts.validates() => {
+                let alloc = self.alloc();
+                quote! { #alloc::string::String }
+            }
+            None => quote! { ::core::convert::Infallible },
+        }
+    }
+
+    /// Where `String` and friends come from, `alloc` has to be declared by `no_std` crates.
+    fn alloc(&self) -> TokenStream {
+        if self.opts.no_std {
+            quote!(::alloc)
+        } else {
+            quote!(::std)
+        }
+    }
+
+    fn build_name(&self) -> Ident {
+        match &self.opts.build_fn.name {
+            Some(name) => name.clone(),
+            None => format_ident!("build"),
+        }
+    }
+
+    /// Binds `__default` to the struct's `Default` impl if any field falls back to it.
+    fn struct_default(&self) -> Option<TokenStream> {
+        let ident = &self.input.ident;
+        let (_, ty_generics, _) = self.input.generics.split_for_impl();
+        self.uses_struct_default().then(|| quote! {
+            let __default: #ident #ty_generics = ::core::default::Default::default();
+        })
+    }
+
+    fn uses_struct_default(&self) -> bool {
+        self.fields
+            .iter()
+            .any(|field| matches!(field.default, Some(FieldDefault::Struct)))
+    }
+
+    /// The bounds only `build` needs, so setters stay usable without them.
+    ///
+    /// Only types mentioning one of the struct's type parameters are bounded, everything else
+    /// either holds anyway or fails to compile in the generated code.
+    fn build_bounds(&self, clone: bool) -> Option<TokenStream> {
+        let bounds = self.bounds(clone);
+        (!bounds.is_empty()).then(|| quote! { where #(#bounds,)* })
+    }
+
+    /// The bounds of [`Self::build_bounds`], without the `where`.
+    fn bounds(&self, clone: bool) -> Vec<TokenStream> {
+        let params = self.input.generics.type_params().map(|param| &param.ident).collect::<Vec<_>>();
+        let ident = &self.input.ident;
+        let (_, ty_generics, _) = self.input.generics.split_for_impl();
+
+        let mut bounds = Vec::new();
+        for field in &self.fields {
+            let value_ty = field.value_ty();
+            let sub_builder = matches!(field.kind, FieldKind::SubBuilder { .. });
+            if clone && !sub_builder && ty::mentions_any(value_ty, &params) {
+                bounds.push(quote! { #value_ty: ::core::clone::Clone });
+            }
+            // The field's builder clones its own fields when built, so their type parameters must be Clone.
+            if sub_builder {
+                for param in &params {
+                    let bound = quote! { #param: ::core::clone::Clone };
+                    let duplicate = bounds.iter().any(|other| other.to_string() == bound.to_string());
+                    if !duplicate && ty::mentions_any(field.ty, &[param]) {
+                        bounds.push(bound);
+                    }
+                }
+            }
+            if let Some(FieldDefault::Trait) = field.default {
+                let ty = field.ty;
+                if ty::mentions_any(ty, &params) {
+                    bounds.push(quote! { #ty: ::core::default::Default });
+                }
+            }
+        }
+        if self.uses_struct_default() && !params.is_empty() {
+            bounds.push(quote! { #ident #ty_generics: ::core::default::Default });
+        }
+
+        bounds
+    }
+
+    /// The struct's generic parameters as arguments, for naming the builder with changed markers.
+    fn generic_args(&self) -> Vec<TokenStream> {
+        self.input
+            .generics
+            .params
+            .iter()
+            .map(|param| match param {
+                GenericParam::Lifetime(param) => {
+                    let lifetime = &param.lifetime;
+                    quote! { #lifetime }
+                }
+                GenericParam::Type(param) => {
+                    let ident = &param.ident;
+                    quote! { #ident }
+                }
+                GenericParam::Const(param) => {
+                    let ident = &param.ident;
+                    quote! { #ident }
+                }
+            })
+            .collect()
+    }
+
+    fn init(&self) -> impl Iterator<Item=TokenStream> + '_ {
+        let fields = self.fields.iter().map(|field| {
+            let field_ident = field.ident;
+            quote! { #field_ident: ::core::option::Option::None }
+        });
+        fields.chain(self.marker_ty().map(|_| quote! { __marker: ::core::marker::PhantomData }))
+    }
+}
+
+/// `try_<setter>`, which converts its argument with `TryInto` and passes it on to the setter.
+fn try_setter(
+    field: &BuilderField,
+    receiver: &TokenStream,
+    returned: &TokenStream,
+    bound: Option<&TokenStream>,
+) -> Option<TokenStream> {
+    if !field.try_setter {
+        return None;
+    }
+
+    let field_ident = field.ident;
+    let setter_name = &field.setter.name;
+    let try_name = format_ident!("try_{}", setter_name.unraw());
+    let ty = field.setter_ty();
+
+    Some(quote! {
+        pub fn #try_name<__Value>(#receiver, #field_ident: __Value)
+            -> ::core::result::Result<#returned, <__Value as ::core::convert::TryInto<#ty>>::Error>
+        where
+            __Value: ::core::convert::TryInto<#ty>,
+            #bound
+        {
+            let #field_ident = ::core::convert::TryInto::try_into(#field_ident)?;
+            ::core::result::Result::Ok(self.#setter_name(#field_ident))
+        }
+    })
+}
+
+/// The parameter type of a field's setter, and the `Option` it stores for the parameter `value`.
+fn setter_input(field: &BuilderField, value: &Ident) -> (TokenStream, TokenStream) {
+    let (param_ty, value) = convert_input(field.setter_ty(), value, field.setter.into);
+    let stored = match field.kind {
+        FieldKind::Optional(_) if !field.setter.strip_option => value,
+        FieldKind::SubBuilder { .. } => stored_value(field, value),
+        _ => quote! { ::core::option::Option::Some(#value) },
+    };
+    (param_ty, stored)
+}
+
+/// The parameters of an `each` setter, and the item it extends the collection with.
+///
+/// Elements are passed as the parameter `value`, map entries as separate `key` and `value`.
+fn each_input(field: &BuilderField, item: &EachItem, value: &Ident) -> (TokenStream, TokenStream) {
+    match item {
+        EachItem::Element(ty) => {
+            let (ty, value_item) = convert_input(ty, value, field.setter.into);
+            (quote! { #value: #ty }, value_item)
+        }
+        EachItem::Entry(key_ty, value_ty) => {
+            let (key_ty, key) = convert_input(key_ty, &format_ident!("key"), field.setter.into);
+            let (value_ty, value) = convert_input(value_ty, &format_ident!("value"), field.setter.into);
+            (quote! { key: #key_ty, value: #value_ty }, quote! { (#key, #value) })
+        }
+    }
+}
+
+fn convert_input(ty: &Type, value: &Ident, into: bool) -> (TokenStream, TokenStream) {
+    if into {
+        (quote! { impl ::core::convert::Into<#ty> }, quote! { ::core::convert::Into::into(#value) })
+    } else {
+        (quote! { #ty }, quote! { #value })
+    }
+}
+
+/// What the builder stores for a field whose value is `value`, the reverse of [`build_value`].
+fn stored_value(field: &BuilderField, value: TokenStream) -> TokenStream {
+    match &field.kind {
+        FieldKind::Optional(_) => value,
+        FieldKind::SubBuilder { builder } => quote! { ::core::option::Option::Some(<#builder>::__from_built(#value)) },
+        _ => quote! { ::core::option::Option::Some(#value) },
+    }
+}
+
+/// `<setter>_mut`, handing out a sub-builder to be filled in place.
+fn sub_builder_name(field: &BuilderField) -> Ident {
+    let name = &field.setter.name;
+    format_ident!("{}_mut", name.unraw(), span = name.span())
+}
+
+/// The expression `build` uses for a field.
+///
+/// `storage` evaluates to the builder's `Option`, `take` turns the `value` bound from it into the
+/// stored value, and `missing` is evaluated if a required field was never set.
+fn build_value(field: &BuilderField, storage: TokenStream, take: TokenStream, missing: TokenStream) -> TokenStream {
+    let field_ident = field.ident;
+    let unset = match (&field.default, &field.kind) {
+        (Some(FieldDefault::Trait), _) => quote! { ::core::default::Default::default() },
+        (Some(FieldDefault::Expr(expr)), _) => quote! { #expr },
+        (Some(FieldDefault::Struct), _) => quote! { __default.#field_ident },
+        (None, FieldKind::Required) => missing,
+        (None, FieldKind::Optional(_)) => quote! { ::core::option::Option::None },
+        (None, FieldKind::Each { .. }) => quote! { ::core::default::Default::default() },
+        // An untouched sub-builder is built anyway, so its missing fields are reported by name.
+        (None, FieldKind::SubBuilder { .. }) => {
+            let ty = field.ty;
+            quote! {{
+                let value = <#ty>::builder();
+                #take
+            }}
+        }
+    };
+
+    let set = match field.kind {
+        FieldKind::Optional(_) => quote! { ::core::option::Option::Some(#take) },
+        _ => take,
+    };
+
+    quote! {
+        match #storage {
+            ::core::option::Option::Some(value) => #set,
+            ::core::option::Option::None => #unset,
+        }
+    }
+}
+
+/// `DataChunk` -> `data_chunk`, as a raw identifier if that's a keyword.
+fn snake_case(ident: &Ident) -> Ident {
+    let mut snake = String::new();
+    for (i, ch) in ident.unraw().to_string().char_indices() {
+        if ch.is_uppercase() {
+            if i != 0 {
+                snake.push('_');
+            }
+            snake.extend(ch.to_lowercase());
+        } else {
+            snake.push(ch);
+        }
+    }
+
+    syn::parse_str(&snake).unwrap_or_else(|_| Ident::new_raw(&snake, ident.span()))
+}
@@ -0,0 +1,165 @@
+//! The builder's view of a single struct field.
+
+use syn::{Ident, Type};
+
+use crate::attr::{FieldDefault, FieldOpts, StructOpts};
+use crate::diagnostic::Errors;
+use crate::ty::{self, EachItem};
+
+pub struct BuilderField<'a> {
+    pub ident: &'a Ident,
+    pub ty: &'a Type,
+    pub kind: FieldKind<'a>,
+    pub default: Option<FieldDefault>,
+    pub setter: Setter,
+    pub try_setter: bool,
+}
+
+/// How the field's setters look, after applying the struct's defaults.
+pub struct Setter {
+    pub name: Ident,
+    pub into: bool,
+    pub strip_option: bool,
+    pub skip: bool,
+}
+
+pub enum FieldKind<'a> {
+    /// Has to be set before the struct can be built.
+    Required,
+    /// An `Option<T>` field, the setter takes the inner `T`.
+    Optional(&'a Type),
+    /// A collection with a setter that extends it by one item at a time.
+    Each { setter: Ident, item: EachItem },
+    /// A struct deriving `Builder`, its builder is stored and built along with this one.
+    SubBuilder { builder: Box<Type> },
+}
+
+impl<'a> BuilderField<'a> {
+    /// Resolves the field's options, recording every problem in `errors` and building the
+    /// field as if the offending option wasn't there.
+    pub fn new(field: &'a syn::Field, struct_opts: &StructOpts, errors: &mut Errors) -> Self {
+        let opts = FieldOpts::from_attrs(&field.attrs, errors);
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+
+        let each = opts.each.and_then(|each| {
+            let item = match each.item {
+                Some(item) => EachItem::Element(Box::new(item)),
+                None => match ty::each_item(ty) {
+                    Some(item) => item,
+                    None => {
+                        errors.push(syn::Error::new_spanned(
+                            ty,
+                            format!(
+                                "`each = \"{}\"` requires a collection like `Vec<T>`, `HashSet<T>` or \
+                                 `HashMap<K, V>`, name the item of other collections with \
+                                 `each(name = \"...\", item = \"...\")`",
+                                each.name,
+                            ),
+                        ));
+                        return None;
+                    }
+                },
+            };
+            Some(FieldKind::Each { setter: each.name, item })
+        });
+        let sub_builder = opts.sub_builder.then(|| match (ty::option_inner(ty), ty::sub_builder(ty)) {
+            (None, Some(builder)) if each.is_none() && !struct_opts.typestate => {
+                Some(FieldKind::SubBuilder { builder: Box::new(builder) })
+            }
+            _ => {
+                let reason = if each.is_some() {
+                    "`sub_builder` conflicts with `each`"
+                } else if struct_opts.typestate {
+                    "`sub_builder` can't report errors from `builder(typestate)` builders"
+                } else {
+                    "`sub_builder` requires a field whose type is a struct deriving `Builder`"
+                };
+                errors.push(syn::Error::new_spanned(ty, reason));
+                None
+            }
+        });
+        let kind = if let Some(Some(sub_builder)) = sub_builder {
+            sub_builder
+        } else if let Some(each) = each {
+            each
+        } else if let Some(inner) = ty::option_inner(ty) {
+            if let Some(span) = opts.default_span {
+                errors.push(syn::Error::new(
+                    span,
+                    "`Option` fields already default to `None`, remove `default` or give another \
+                     value with `default = \"...\"`",
+                ));
+            }
+            FieldKind::Optional(inner)
+        } else {
+            FieldKind::Required
+        };
+
+        let setter = Setter {
+            name: opts.setter.name.unwrap_or_else(|| ident.clone()),
+            into: opts.setter.into.or(struct_opts.setter.into).unwrap_or(false),
+            strip_option: opts.setter.strip_option.or(struct_opts.setter.strip_option).unwrap_or(true),
+            skip: opts.setter.skip,
+        };
+        if setter.skip {
+            if let FieldKind::Each { setter, .. } = &kind {
+                errors.push(syn::Error::new_spanned(setter, "`each` conflicts with `setter(skip)`"));
+            }
+        }
+
+        let default = match opts.default {
+            None if struct_opts.default => Some(FieldDefault::Struct),
+            // A skipped field can only ever be built from its default.
+            None if setter.skip && matches!(kind, FieldKind::Required) => Some(FieldDefault::Trait),
+            default => default,
+        };
+
+        let field = Self { ident, ty, kind, default, setter, try_setter: opts.try_setter };
+        if field.try_setter && !field.has_field_setter() {
+            errors.push(syn::Error::new_spanned(ident, "`try_setter` requires the field's setter"));
+        }
+
+        field
+    }
+
+    /// Whether `build` can't succeed without this field being set.
+    pub fn is_required(&self) -> bool {
+        matches!(self.kind, FieldKind::Required) && self.default.is_none()
+    }
+
+    /// The type the builder stores inside its `Option`.
+    pub fn value_ty(&self) -> &'a Type {
+        match self.kind {
+            FieldKind::Optional(inner) => inner,
+            _ => self.ty,
+        }
+    }
+
+    /// The type the builder stores inside its `Option`, the field's builder for sub-builders.
+    pub fn stored_ty(&self) -> &Type {
+        match &self.kind {
+            FieldKind::SubBuilder { builder, .. } => builder,
+            _ => self.value_ty(),
+        }
+    }
+
+    /// The type the setter for the whole field accepts, before `into` is applied.
+    pub fn setter_ty(&self) -> &'a Type {
+        match self.kind {
+            FieldKind::Optional(_) if !self.setter.strip_option => self.ty,
+            _ => self.value_ty(),
+        }
+    }
+
+    /// Whether a setter taking the whole value should be generated.
+    ///
+    /// It is left out if an `each` setter already uses the same name.
+    pub fn has_field_setter(&self) -> bool {
+        match &self.kind {
+            _ if self.setter.skip => false,
+            FieldKind::Each { setter, .. } => *setter != self.setter.name,
+            _ => true,
+        }
+    }
+}
@@ -1,8 +1,70 @@
 use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
 
-#[proc_macro_derive(Builder)]
+mod attr;
+mod diagnostic;
+mod expand;
+mod field;
+mod ty;
+
+/// Derives a `<Name>Builder` type and a `<Name>::builder()` constructor.
+///
+/// On enums, every variant with named fields gets a `<Name><Variant>Builder`, created through
+/// `<Name>Builder::<variant>()` with the variant's name in snake case.
+///
+/// Every builder has a `merge(&mut self, other)`, taking all fields that are set in `other`.
+///
+/// Structs can be turned back into a builder with every field set, by cloning them with
+/// `to_builder(&self)`, which requires `Clone` fields, or by moving them with `From`.
+///
+/// `build()` returns a `<Name>BuilderError`, which is either `MissingField(name)` or the
+/// `Validation(E)` error of the struct's `validate` function. Typestate builders can't miss a
+/// field and have no error type: their `build()` returns the struct itself, or `Result<Name, E>`
+/// with the bare validation error if there is a `validate` or `async_validate` function.
+///
+/// Struct attributes:
+/// - `#[builder(typestate)]`: every required field is tracked in the builder's type, so `build()`
+///   is only callable once all of them have been set.
+/// - `#[builder(pattern = "mutable" | "owned" | "immutable")]`: setters take `&mut self` (the
+///   default), consume `self`, or return a modified clone of `&self`. Only owned builders move
+///   their fields into the struct, the others clone them in `build()`.
+/// - `#[builder(default)]`: unset fields fall back to their value in the struct's `Default` impl.
+/// - `#[builder(validate = "path::to::fn")]`: a `fn(&Name) -> Result<(), E>` run at the end of
+///   `build()`. `E` is `String` unless set with `#[builder(validation_error = "Type")]`.
+/// - `#[builder(build_fn(name = "...", async_validate = "path::to::fn"))]`: renames `build()`, or
+///   makes it an `async fn` awaiting `async fn(&Name) -> Result<(), E>` after `validate`.
+/// - `#[builder(derive(Trait, ...))]`: derives forwarded to the builder, whose fields are all
+///   `Option`s, e.g. `Deserialize` for loading partial configs.
+/// - `#[builder(setter(into, strip_option = false))]`: defaults for every field's setter.
+/// - `#[builder(no_std)]`: the generated code only uses `core` and `alloc`, the latter has to be
+///   declared with `extern crate alloc;`.
+/// - `#[builder(crate = "path::to::derive_builder")]`: where a facade crate re-exports this one.
+///   The expansion only uses paths starting at `::core`, `::std` or `::alloc`, so this is merely
+///   checked to resolve.
+///
+/// Field attributes:
+/// - `#[builder(each = "name")]`: generates a setter that adds one item at a time to a std
+///   collection, maps take a key and a value. Other `Default + Extend<T>` collections use
+///   `#[builder(each(name = "name", item = "T"))]`.
+/// - `#[builder(default)]`, `#[builder(default = "expr")]`: the value used if the field is never
+///   set, either `Default::default()` or `expr` evaluated during `build()`.
+/// - `#[builder(setter(...))]`: `into` accepts `impl Into<T>`, `strip_option = false` makes
+///   `Option<T>` fields take the whole `Option`, `name = "..."` renames the setter and `skip` leaves
+///   it out so the field is always built from its default.
+/// - `#[builder(try_setter)]`: adds `try_<setter>` taking any `TryInto<T>` and returning the
+///   conversion's error.
+/// - `#[builder(sub_builder)]`: for fields whose type derives `Builder`, `<setter>_mut()` hands out
+///   the field's builder, which is built by `build()`. Its errors are reported as
+///   `MissingNestedField(path)` or `InvalidNestedField(path, message)`, e.g. `server.tls.cert`.
+///   The field's builder may use any pattern or build method, but not `typestate`, and its
+///   `async_validate` is only run when it's built on its own. With `pattern = "immutable"` the
+///   field's builder has to be `Clone`, e.g. through `#[builder(derive(Clone))]`. `merge` merges
+///   sub-builders set on both sides.
+#[proc_macro_derive(Builder, attributes(builder))]
 pub fn derive(input: TokenStream) -> TokenStream {
-    let _ = input;
+    let input = parse_macro_input!(input as DeriveInput);
 
-    unimplemented!()
+    expand::derive(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
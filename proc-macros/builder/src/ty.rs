@@ -0,0 +1,99 @@
+//! Helpers for inspecting field types.
+//!
+//! Macros only ever see tokens, so these look at how a type is spelled (`Option<T>`,
+//! `std::vec::Vec<T>`, ...) and not at what it resolves to.
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{format_ident, ToTokens};
+use syn::{GenericArgument, Ident, PathArguments, PathSegment, Type, TypePath};
+
+/// Returns `T` if `ty` is spelled as `Option<T>`.
+pub fn option_inner(ty: &Type) -> Option<&Type> {
+    generic_inner(ty, "Option")
+}
+
+/// What an `each` setter adds to a collection.
+pub enum EachItem {
+    /// A single element, as in `Vec<T>` or `HashSet<T>`.
+    Element(Box<Type>),
+    /// A key and a value, as in `HashMap<K, V>`.
+    Entry(Box<Type>, Box<Type>),
+}
+
+/// Returns the item of a field spelled as one of the std collections.
+///
+/// Other collections have to name their item with `each(item = "...")`.
+pub fn each_item(ty: &Type) -> Option<EachItem> {
+    let segment = last_segment(ty)?;
+    let args = type_args(segment);
+
+    match (segment.ident.to_string().as_str(), &args[..]) {
+        ("Vec" | "VecDeque" | "LinkedList" | "BinaryHeap" | "HashSet" | "BTreeSet", [item, ..]) => {
+            Some(EachItem::Element(Box::new((*item).clone())))
+        }
+        ("HashMap" | "BTreeMap", [key, value, ..]) => {
+            Some(EachItem::Entry(Box::new((*key).clone()), Box::new((*value).clone())))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the `<Name>Builder` generated for a field of type `Name`.
+pub fn sub_builder(ty: &Type) -> Option<Type> {
+    let Type::Path(path) = ty else { return None };
+    if path.qself.is_some() {
+        return None;
+    }
+
+    let mut builder = path.path.clone();
+    let segment = builder.segments.last_mut()?;
+    segment.ident = format_ident!("{}Builder", segment.ident);
+
+    Some(Type::Path(TypePath { qself: None, path: builder }))
+}
+
+/// Returns the single type argument of `ty` if its last path segment is `wrapper`.
+fn generic_inner<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let segment = last_segment(ty)?;
+    if segment.ident != wrapper {
+        return None;
+    }
+
+    match type_args(segment)[..] {
+        [inner] => Some(inner),
+        _ => None,
+    }
+}
+
+fn last_segment(ty: &Type) -> Option<&PathSegment> {
+    let Type::Path(path) = ty else { return None };
+    if path.qself.is_some() {
+        return None;
+    }
+    path.path.segments.last()
+}
+
+/// The type arguments of `segment`, ignoring lifetimes and const arguments.
+fn type_args(segment: &PathSegment) -> Vec<&Type> {
+    let PathArguments::AngleBracketed(args) = &segment.arguments else { return Vec::new() };
+    args.args
+        .iter()
+        .filter_map(|arg| match arg {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether any of the generic `params` appears somewhere in `ty`.
+pub fn mentions_any(ty: &Type, params: &[&Ident]) -> bool {
+    fn walk(tokens: TokenStream, params: &[&Ident]) -> bool {
+        tokens.into_iter().any(|token| match token {
+            TokenTree::Ident(ident) => params.iter().any(|param| **param == ident),
+            TokenTree::Group(group) => walk(group.stream(), params),
+            _ => false,
+        })
+    }
+
+    walk(ty.to_token_stream(), params)
+}
@@ -0,0 +1,45 @@
+// With #[builder(typestate)] every required field is tracked in the type of the
+// builder. Setters consume the builder and return it with the field's marker
+// flipped, and `build` is only available once every field that is neither an
+// Option nor an `each` collection has been set. Because a missing field can no
+// longer happen at runtime, `build` returns the struct directly.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(each = "env")]
+    env: Vec<String>,
+    current_dir: Option<String>,
+    timeout: u64,
+}
+
+fn main() {
+    let command = Command::builder()
+        .arg("build".to_owned())
+        .executable("cargo".to_owned())
+        .arg("--release".to_owned())
+        .timeout(30)
+        .build();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert!(command.env.is_empty());
+    assert!(command.current_dir.is_none());
+    assert_eq!(command.timeout, 30);
+
+    // Setting a field a second time keeps the builder complete.
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .timeout(30)
+        .current_dir("..".to_owned())
+        .executable("rustc".to_owned())
+        .build();
+
+    assert_eq!(command.executable, "rustc");
+    assert_eq!(command.current_dir.as_deref(), Some(".."));
+}
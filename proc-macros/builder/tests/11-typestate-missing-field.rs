@@ -0,0 +1,20 @@
+// Forgetting a required field of a #[builder(typestate)] builder is a compile
+// error instead of an Err returned from `build`.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let _command = Command::builder()
+        .arg("build".to_owned())
+        .current_dir("..".to_owned())
+        .build();
+}
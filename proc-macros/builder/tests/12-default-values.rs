@@ -0,0 +1,92 @@
+// Fields can fall back to a default value instead of having to be set.
+//
+// #[builder(default)] on a field uses the field type's Default impl, while
+// #[builder(default = "...")] takes an arbitrary expression that is evaluated
+// each time `build` is called. #[builder(default)] on the struct makes every
+// field without its own default fall back to its value in the struct's
+// Default impl.
+//
+// A defaulted field is never missing, so with #[builder(typestate)] it doesn't
+// have to be set before calling `build`.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(default)]
+    args: Vec<String>,
+    #[builder(default = "vec![\"PATH=/usr/bin\".to_owned()]")]
+    env: Vec<String>,
+    #[builder(default = "Some(\"..\".to_owned())")]
+    current_dir: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(default)]
+pub struct Limits {
+    memory: u64,
+    #[builder(default = "4")]
+    threads: usize,
+    label: Option<String>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            memory: 1024,
+            threads: 1,
+            label: Some("default".to_owned()),
+        }
+    }
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Job {
+    name: String,
+    #[builder(default = "3")]
+    retries: u32,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap();
+
+    assert!(command.args.is_empty());
+    assert_eq!(command.env, vec!["PATH=/usr/bin"]);
+    assert_eq!(command.current_dir.as_deref(), Some(".."));
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .env(vec![])
+        .current_dir("/".to_owned())
+        .build()
+        .unwrap();
+
+    assert!(command.env.is_empty());
+    assert_eq!(command.current_dir.as_deref(), Some("/"));
+
+    assert!(Command::builder().build().is_err());
+
+    let limits = Limits::builder().build().unwrap();
+    assert_eq!(limits.memory, 1024);
+    assert_eq!(limits.threads, 4);
+    assert_eq!(limits.label.as_deref(), Some("default"));
+
+    let limits = Limits::builder()
+        .memory(64)
+        .label("small".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(limits.memory, 64);
+    assert_eq!(limits.label.as_deref(), Some("small"));
+
+    let job = Job::builder().name("deploy".to_owned()).build();
+    assert_eq!(job.retries, 3);
+
+    let job = Job::builder().retries(0).name("deploy".to_owned()).build();
+    assert_eq!(job.retries, 0);
+}
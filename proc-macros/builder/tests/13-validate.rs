@@ -0,0 +1,98 @@
+// `build` reports errors through a generated `<Name>BuilderError` enum, so
+// callers can match on which field was missing.
+//
+// #[builder(validate = "path::to::fn")] runs a `fn(&T) -> Result<(), E>` on
+// the assembled struct before it is returned, and wraps its error in the
+// `Validation` variant. `E` is `String` unless another type is given through
+// #[builder(validation_error = "...")].
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(validate = "validate_command")]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+}
+
+fn validate_command(command: &Command) -> Result<(), String> {
+    if command.executable.is_empty() {
+        return Err("executable must not be empty".to_owned());
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PortError {
+    Reserved(u16),
+}
+
+impl std::fmt::Display for PortError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PortError::Reserved(port) => write!(f, "port {} is reserved", port),
+        }
+    }
+}
+
+mod checks {
+    pub fn port(server: &super::Server) -> Result<(), super::PortError> {
+        if server.port < 1024 {
+            return Err(super::PortError::Reserved(server.port));
+        }
+        Ok(())
+    }
+
+    pub fn listener(listener: &super::Listener) -> Result<(), super::PortError> {
+        if listener.port < 1024 {
+            return Err(super::PortError::Reserved(listener.port));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Builder)]
+#[builder(validate = "checks::port", validation_error = "PortError")]
+pub struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Builder)]
+#[builder(typestate, validate = "checks::listener", validation_error = "PortError")]
+pub struct Listener {
+    port: u16,
+}
+
+fn main() {
+    let err = Command::builder().arg("build".to_owned()).build().err().unwrap();
+    assert_eq!(err, CommandBuilderError::MissingField("executable"));
+    assert_eq!(err.to_string(), "missing field `executable`");
+
+    let err = Command::builder().executable(String::new()).build().err().unwrap();
+    assert_eq!(
+        err,
+        CommandBuilderError::Validation("executable must not be empty".to_owned()),
+    );
+
+    let command = Command::builder().executable("cargo".to_owned()).build().unwrap();
+    assert_eq!(command.executable, "cargo");
+
+    let err = Server::builder()
+        .host("localhost".to_owned())
+        .port(80)
+        .build()
+        .err()
+        .unwrap();
+    match err {
+        ServerBuilderError::Validation(PortError::Reserved(port)) => assert_eq!(port, 80),
+        ServerBuilderError::MissingField(field) => panic!("missing {}", field),
+    }
+
+    let boxed: Box<dyn std::error::Error> = Server::builder().port(8080).build().err().unwrap().into();
+    assert_eq!(boxed.to_string(), "missing field `host`");
+
+    assert_eq!(Listener::builder().port(22).build().err(), Some(PortError::Reserved(22)));
+    assert_eq!(Listener::builder().port(8080).build().unwrap().port, 8080);
+}
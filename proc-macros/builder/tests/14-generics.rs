@@ -0,0 +1,83 @@
+// Generic structs carry their lifetimes, type and const parameters and where
+// clauses over to the builder and its impls.
+//
+// Only `build` needs more than the struct's own bounds: the runtime checked
+// builder clones its fields, so it requires `Clone` for every field type that
+// mentions a type parameter. Setters can be called on a builder of non-Clone
+// values, and the typestate builder, which moves its fields, needs no extra
+// bounds at all.
+
+use derive_builder::Builder;
+
+pub trait Body {
+    fn len(&self) -> usize;
+}
+
+impl Body for String {
+    fn len(&self) -> usize {
+        str::len(self)
+    }
+}
+
+#[derive(Builder)]
+pub struct Request<'a, B: Body>
+where
+    B: Send,
+{
+    method: &'a str,
+    body: B,
+    #[builder(each = "header")]
+    headers: Vec<(&'a str, &'a str)>,
+    timeout: Option<u64>,
+}
+
+#[derive(Builder)]
+pub struct Buffer<T = u8, const N: usize = 4> {
+    data: [T; N],
+    #[builder(default)]
+    spill: Vec<T>,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Stream<'a, B: Body> {
+    name: &'a str,
+    body: B,
+}
+
+// Neither `Clone` nor `Default`.
+pub struct Upload(String);
+
+impl Body for Upload {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+fn main() {
+    let request = Request::builder()
+        .method("POST")
+        .body("hello".to_owned())
+        .header(("Accept", "*/*"))
+        .build()
+        .unwrap();
+
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.body.len(), 5);
+    assert_eq!(request.headers, vec![("Accept", "*/*")]);
+    assert!(request.timeout.is_none());
+
+    let mut builder = Request::builder();
+    builder.method("PUT").body(Upload("data".to_owned()));
+
+    let buffer: Buffer = Buffer::builder().data([1, 2, 3, 4]).build().unwrap();
+    assert_eq!(buffer.data, [1, 2, 3, 4]);
+    assert!(buffer.spill.is_empty());
+
+    let stream = Stream::builder()
+        .body(Upload("chunk".to_owned()))
+        .name("upload")
+        .build();
+    assert_eq!(stream.name, "upload");
+    assert_eq!(stream.body.len(), 5);
+}
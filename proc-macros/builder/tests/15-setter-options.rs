@@ -0,0 +1,63 @@
+// Setters can be tuned through #[builder(setter(...))]:
+//
+//   - `into` accepts `impl Into<T>`, on the struct it applies to every field
+//     and can be turned off again per field with `into = false`.
+//   - `strip_option = false` makes the setter of an `Option<T>` field take the
+//     whole `Option<T>` instead of `T`.
+//   - `name = "..."` renames the setter.
+//   - `skip` removes the setter, the field is always built from its default.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(setter(into))]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(setter(strip_option = false))]
+    current_dir: Option<String>,
+    #[builder(setter(into = false, name = "timeout_secs"))]
+    timeout: u64,
+    #[builder(setter(skip))]
+    retries: u32,
+    #[builder(setter(skip), default = "\"generated\".to_owned()")]
+    origin: String,
+}
+
+#[derive(Builder)]
+pub struct Server {
+    #[builder(setter(strip_option = false))]
+    host: Option<String>,
+    port: Option<u16>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo")
+        .arg("build")
+        .arg(String::from("--release"))
+        .current_dir(Some("..".to_owned()))
+        .timeout_secs(30)
+        .build()
+        .unwrap();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.current_dir.as_deref(), Some(".."));
+    assert_eq!(command.timeout, 30);
+    assert_eq!(command.retries, 0);
+    assert_eq!(command.origin, "generated");
+
+    let command = Command::builder()
+        .executable("cargo")
+        .current_dir(None)
+        .timeout_secs(30)
+        .build()
+        .unwrap();
+    assert!(command.current_dir.is_none());
+
+    let server = Server::builder().host(None).port(8080).build().unwrap();
+    assert!(server.host.is_none());
+    assert_eq!(server.port, Some(8080));
+}
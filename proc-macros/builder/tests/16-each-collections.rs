@@ -0,0 +1,73 @@
+// `each` setters work for every std collection, not only Vec. Maps take a key
+// and a value, everything else takes a single element. The setters add items
+// through `Extend`, so any other collection implementing Default and Extend
+// can be used by naming its item with #[builder(each(name = "...", item = "..."))].
+
+use derive_builder::Builder;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct Flags {
+    bits: u32,
+}
+
+impl Extend<u8> for Flags {
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, iter: I) {
+        for bit in iter {
+            self.bits |= 1 << bit;
+        }
+    }
+}
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "env")]
+    env: HashMap<String, String>,
+    #[builder(each = "label")]
+    labels: BTreeMap<&'static str, u32>,
+    #[builder(each = "feature")]
+    features: HashSet<String>,
+    #[builder(each = "target")]
+    targets: BTreeSet<&'static str>,
+    #[builder(each = "step", setter(into))]
+    steps: VecDeque<String>,
+    #[builder(each(name = "flag", item = "u8"))]
+    flags: Flags,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .env("RUST_LOG".to_owned(), "debug".to_owned())
+        .env("CARGO_TERM_COLOR".to_owned(), "always".to_owned())
+        .label("priority", 1)
+        .feature("serde".to_owned())
+        .feature("serde".to_owned())
+        .target("wasm32-unknown-unknown")
+        .target("x86_64-unknown-linux-gnu")
+        .step("fetch")
+        .step("build")
+        .flag(0)
+        .flag(3)
+        .build()
+        .unwrap();
+
+    assert_eq!(command.env.len(), 2);
+    assert_eq!(command.env["RUST_LOG"], "debug");
+    assert_eq!(command.labels.get("priority"), Some(&1));
+    assert_eq!(command.features.len(), 1);
+    assert_eq!(
+        command.targets.into_iter().collect::<Vec<_>>(),
+        vec!["wasm32-unknown-unknown", "x86_64-unknown-linux-gnu"],
+    );
+    assert_eq!(command.steps, vec!["fetch", "build"]);
+    assert_eq!(command.flags, Flags { bits: 0b1001 });
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap();
+    assert!(command.env.is_empty());
+    assert_eq!(command.flags, Flags::default());
+}
@@ -0,0 +1,62 @@
+// #[builder(pattern = "...")] chooses how setters and `build` take the builder.
+//
+//   - "mutable" (the default) takes `&mut self` and `build` clones the fields.
+//   - "owned" consumes the builder in every setter and in `build`, so fields
+//     are moved into the struct and don't have to implement Clone.
+//   - "immutable" takes `&self` and returns a modified copy of the builder, so
+//     a partially filled builder can be reused as a template.
+
+use derive_builder::Builder;
+
+// Can't be cloned, like a `File`.
+pub struct Handle(u32);
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Connection {
+    handle: Handle,
+    #[builder(each = "option")]
+    options: Vec<String>,
+    name: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "immutable")]
+pub struct Request {
+    method: String,
+    path: String,
+    #[builder(each = "header")]
+    headers: Vec<String>,
+}
+
+fn main() {
+    let connection = Connection::builder()
+        .handle(Handle(3))
+        .option("nodelay".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(connection.handle.0, 3);
+    assert_eq!(connection.options, vec!["nodelay"]);
+    assert!(connection.name.is_none());
+
+    assert!(Connection::builder().name("unset".to_owned()).build().is_err());
+
+    let template = Request::builder()
+        .method("GET".to_owned())
+        .header("Accept: */*".to_owned());
+
+    let index = template.path("/".to_owned()).build().unwrap();
+    let about = template
+        .path("/about".to_owned())
+        .header("Cache-Control: no-cache".to_owned())
+        .build()
+        .unwrap();
+
+    assert_eq!(index.path, "/");
+    assert_eq!(index.headers, vec!["Accept: */*"]);
+    assert_eq!(about.path, "/about");
+    assert_eq!(about.headers.len(), 2);
+
+    // The template itself was never modified.
+    assert!(template.build().is_err());
+}
@@ -0,0 +1,80 @@
+// On an enum, every variant with named fields gets its own builder with the
+// same setters as a struct builder would have, and its `build` returns the
+// enum. The variant builders are created through functions on `<Enum>Builder`
+// named after the variant in snake case. Tuple and unit variants are skipped.
+//
+// Variant builders carry all of the enum's generic parameters, even those
+// their own fields don't use.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+pub enum Message {
+    Ping {
+        id: u64,
+    },
+    DataChunk {
+        stream: u32,
+        #[builder(each = "byte")]
+        payload: Vec<u8>,
+        compressed: Option<bool>,
+    },
+    Text(String),
+    Close,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(typestate)]
+pub enum Event<T> {
+    Value { key: String, value: T },
+    Tick { at: u64 },
+}
+
+#[derive(Builder, Debug, PartialEq)]
+pub enum Envelope<'a, T, E> {
+    Ping { id: u32 },
+    Data { value: T, tag: &'a str },
+    Failed { error: E },
+}
+
+fn main() {
+    let ping = MessageBuilder::ping().id(7).build().unwrap();
+    assert_eq!(ping, Message::Ping { id: 7 });
+
+    let data = MessageBuilder::data_chunk()
+        .stream(1)
+        .byte(0xde)
+        .byte(0xad)
+        .build()
+        .unwrap();
+    assert_eq!(
+        data,
+        Message::DataChunk {
+            stream: 1,
+            payload: vec![0xde, 0xad],
+            compressed: None,
+        },
+    );
+
+    let err = MessageBuilder::data_chunk().byte(0).build().unwrap_err();
+    assert_eq!(err, MessageDataChunkBuilderError::MissingField("stream"));
+
+    let event = EventBuilder::value().value(3).key("count".to_owned()).build();
+    assert_eq!(
+        event,
+        Event::Value {
+            key: "count".to_owned(),
+            value: 3,
+        },
+    );
+
+    let tick: Event<String> = EventBuilder::tick().at(5).build();
+    assert_eq!(tick, Event::Tick { at: 5 });
+
+    let ping: Envelope<u8, String> = EnvelopeBuilder::ping().id(1).build().unwrap();
+    assert_eq!(ping, Envelope::Ping { id: 1 });
+    let data: Envelope<u8, String> = EnvelopeBuilder::data().value(2).tag("t").build().unwrap();
+    assert_eq!(data, Envelope::Data { value: 2, tag: "t" });
+    let failed: Envelope<u8, &str> = EnvelopeBuilder::failed().error("boom").build().unwrap();
+    assert_eq!(failed, Envelope::Failed { error: "boom" });
+}
@@ -0,0 +1,112 @@
+// #[builder(try_setter)] adds a `try_<setter>` next to the field's setter. It
+// accepts anything that converts into the field with TryInto and returns the
+// conversion error instead of setting the field.
+//
+// #[builder(build_fn(name = "..."))] renames `build`, and
+// #[builder(build_fn(async_validate = "..."))] turns it into an `async fn`
+// that awaits the given `async fn(&T) -> Result<(), E>` after the synchronous
+// `validate`.
+
+use derive_builder::Builder;
+use std::convert::TryFrom;
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Url(String);
+
+impl TryFrom<&str> for Url {
+    type Error = String;
+
+    fn try_from(url: &str) -> Result<Self, Self::Error> {
+        if url.contains("://") {
+            Ok(Url(url.to_owned()))
+        } else {
+            Err(format!("`{}` is missing a scheme", url))
+        }
+    }
+}
+
+#[derive(Builder)]
+#[builder(build_fn(name = "finish"))]
+pub struct Endpoint {
+    #[builder(try_setter)]
+    url: Url,
+    #[builder(try_setter)]
+    port: u16,
+    #[builder(try_setter, setter(name = "retry_limit"))]
+    retries: Option<u8>,
+}
+
+#[derive(Builder)]
+#[builder(typestate, build_fn(async_validate = "check_reachable"))]
+pub struct Probe {
+    #[builder(try_setter)]
+    url: Url,
+}
+
+async fn check_reachable(probe: &Probe) -> Result<(), String> {
+    if probe.url.0.starts_with("https://") {
+        Ok(())
+    } else {
+        Err("unreachable".to_owned())
+    }
+}
+
+#[derive(Builder)]
+#[builder(pattern = "owned", build_fn(async_validate = "check_service"))]
+pub struct Service {
+    name: String,
+}
+
+async fn check_service(service: &Service) -> Result<(), String> {
+    if service.name.is_empty() {
+        Err("empty name".to_owned())
+    } else {
+        Ok(())
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+fn main() {
+    let endpoint = Endpoint::builder()
+        .try_url("https://example.com")
+        .unwrap()
+        .try_port(8080u32)
+        .unwrap()
+        .try_retry_limit(3u64)
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    assert_eq!(endpoint.url, Url("https://example.com".to_owned()));
+    assert_eq!(endpoint.port, 8080);
+    assert_eq!(endpoint.retries, Some(3));
+
+    let mut builder = Endpoint::builder();
+    assert_eq!(builder.try_url("example.com").err().unwrap(), "`example.com` is missing a scheme");
+    assert!(builder.try_port(70000u32).is_err());
+    assert!(builder.finish().is_err());
+
+    let probe = block_on(Probe::builder().try_url("https://example.com").unwrap().build()).unwrap();
+    assert_eq!(probe.url.0, "https://example.com");
+
+    let probe = block_on(Probe::builder().try_url("http://example.com").unwrap().build());
+    assert_eq!(probe.err().unwrap(), "unreachable");
+
+    let service = block_on(Service::builder().name("api".to_owned()).build()).unwrap();
+    assert_eq!(service.name, "api");
+
+    let err = block_on(Service::builder().name(String::new()).build()).err().unwrap();
+    assert_eq!(err, ServiceBuilderError::Validation("empty name".to_owned()));
+}
@@ -0,0 +1,59 @@
+// #[builder(derive(...))] forwards derives to the builder. Since every field
+// of the builder is an Option, deriving Deserialize lets a config file that
+// only sets some fields be loaded into a builder.
+//
+// `merge` combines two builders: every field that is set in the argument
+// replaces the one in `self`, so config layers can be applied on top of each
+// other before `build` checks that everything required ended up being set.
+
+use derive_builder::Builder;
+use serde::Deserialize;
+
+#[derive(Builder, Debug)]
+#[builder(derive(Deserialize, Debug, Clone))]
+pub struct Config {
+    host: String,
+    port: u16,
+    #[builder(each = "feature")]
+    features: Vec<String>,
+    log_level: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "immutable", derive(Clone, Debug))]
+pub struct Template {
+    name: String,
+}
+
+fn main() {
+    let defaults: ConfigBuilder = serde_json::from_str(r#"{ "port": 8080, "features": ["gzip"] }"#).unwrap();
+    let system: ConfigBuilder = serde_json::from_str(r#"{ "host": "0.0.0.0", "log_level": "warn" }"#).unwrap();
+    let user: ConfigBuilder = serde_json::from_str(r#"{ "log_level": "debug" }"#).unwrap();
+
+    assert!(defaults.clone().build().is_err());
+    assert_eq!(
+        format!("{:?}", defaults),
+        r#"ConfigBuilder { host: None, port: Some(8080), features: Some(["gzip"]), log_level: None }"#,
+    );
+
+    let mut layered = defaults.clone();
+    layered.merge(system).merge(user);
+    let config = layered.build().unwrap();
+
+    assert_eq!(config.host, "0.0.0.0");
+    assert_eq!(config.port, 8080);
+    assert_eq!(config.features, vec!["gzip"]);
+    assert_eq!(config.log_level.as_deref(), Some("debug"));
+
+    let config = defaults
+        .clone()
+        .host("localhost".to_owned())
+        .merge(Config::builder())
+        .build()
+        .unwrap();
+    assert_eq!(config.host, "localhost");
+    assert!(config.log_level.is_none());
+
+    let template = Template::builder().name("base".to_owned());
+    assert_eq!(template.clone().build().unwrap().name, "base");
+}
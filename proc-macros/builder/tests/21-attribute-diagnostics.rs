@@ -0,0 +1,27 @@
+// Every misuse of #[builder(...)] is reported in one go, each error pointing at
+// the option responsible for it. Typos get a suggestion for the option that was
+// probably meant.
+
+use derive_builder::Builder;
+use std::collections::HashMap;
+
+#[derive(Builder)]
+#[builder(patern = "owned")]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg", setter(int))]
+    args: Vec<String>,
+    // Already has an `arg` setter from `args`.
+    #[builder(setter(name = "arg"))]
+    first_arg: String,
+    #[builder(each = "env-var")]
+    env: HashMap<String, String>,
+    #[builder(each = "word")]
+    description: String,
+    #[builder(default)]
+    current_dir: Option<String>,
+    #[builder(defualt)]
+    timeout: u64,
+}
+
+fn main() {}
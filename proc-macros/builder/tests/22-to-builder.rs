@@ -0,0 +1,56 @@
+// An existing value can be turned back into a builder, either by cloning every
+// field with `to_builder` or by moving them with `From`. All fields start out
+// set, so tweaking one of them and calling `build` again gives a copy with just
+// that change.
+//
+// For typestate builders the returned builder has every required field marked
+// as set, so `build` can be called on it right away.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+pub struct Request {
+    url: String,
+    timeout: u64,
+    #[builder(each = "header")]
+    headers: Vec<String>,
+    body: Option<String>,
+    #[builder(setter(skip))]
+    retries: u32,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(typestate)]
+pub struct Point<T> {
+    x: T,
+    y: T,
+    label: Option<String>,
+}
+
+fn main() {
+    let request = Request::builder()
+        .url("https://example.com".to_owned())
+        .timeout(10)
+        .header("Accept: */*".to_owned())
+        .body("{}".to_owned())
+        .build()
+        .unwrap();
+
+    let slow = request.to_builder().timeout(60).build().unwrap();
+    assert_eq!(slow.url, request.url);
+    assert_eq!(slow.timeout, 60);
+    assert_eq!(slow.body, request.body);
+
+    let mut builder = RequestBuilder::from(slow);
+    builder.header("Connection: close".to_owned());
+    let request = builder.build().unwrap();
+    assert_eq!(request.headers, vec!["Accept: */*", "Connection: close"]);
+    assert_eq!(request.retries, 0);
+
+    let point = Point::builder().x(1).y(2).build();
+    let moved = point.to_builder().y(5).build();
+    assert_eq!(moved, Point { x: 1, y: 5, label: None });
+
+    let builder: PointBuilder<i32, true, true> = moved.into();
+    assert_eq!(builder.label("b".to_owned()).build().label.as_deref(), Some("b"));
+}
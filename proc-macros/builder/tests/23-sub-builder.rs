@@ -0,0 +1,87 @@
+// A field whose type derives Builder itself can be marked #[builder(sub_builder)].
+// The parent then stores the field's builder instead of the finished value and
+// hands it out through `<field>_mut`, so nested configs are filled in place
+// instead of building every leaf first and passing it up.
+//
+// The sub-builders are built by the parent's `build`. When one of them fails,
+// the error names the full path to the culprit, e.g. `missing field
+// `tls.cert``. Setting the whole value through the field's setter still works.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+pub struct Tls {
+    cert: String,
+    key: Option<String>,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(validate = "Server::validate")]
+pub struct Server {
+    host: String,
+    port: u16,
+    #[builder(sub_builder)]
+    tls: Tls,
+}
+
+impl Server {
+    fn validate(&self) -> Result<(), String> {
+        if self.port == 0 {
+            return Err("port must not be 0".to_owned());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Builder, Debug)]
+pub struct Config {
+    name: String,
+    #[builder(sub_builder)]
+    server: Server,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+pub struct Limit<T> {
+    max: T,
+}
+
+#[derive(Builder, Debug)]
+pub struct Pool<T> {
+    #[builder(sub_builder)]
+    limit: Limit<T>,
+}
+
+fn main() {
+    let mut builder = Config::builder();
+    builder.name("api".to_owned());
+    builder.server_mut().host("localhost".to_owned()).port(443);
+    builder.server_mut().tls_mut().key("key.pem".to_owned());
+
+    let err = builder.build().unwrap_err();
+    assert_eq!(err, ConfigBuilderError::MissingNestedField("server.tls.cert".to_owned()));
+    assert_eq!(err.to_string(), "missing field `server.tls.cert`");
+
+    builder.server_mut().tls_mut().cert("cert.pem".to_owned());
+    let config = builder.build().unwrap();
+    assert_eq!(config.server.tls, Tls { cert: "cert.pem".to_owned(), key: Some("key.pem".to_owned()) });
+
+    builder.server_mut().port(0);
+    let err = builder.build().unwrap_err();
+    assert_eq!(err.to_string(), "invalid field `server`: port must not be 0");
+
+    // Nested builders are built even if they were never touched.
+    let err = Config::builder().name("empty".to_owned()).build().unwrap_err();
+    assert_eq!(err.to_string(), "missing field `server.host`");
+
+    let server = Server::builder()
+        .host("localhost".to_owned())
+        .port(80)
+        .tls(Tls { cert: "cert.pem".to_owned(), key: None })
+        .build()
+        .unwrap();
+    assert_eq!(server.tls.cert, "cert.pem");
+
+    let mut builder = Pool::<u32>::builder();
+    builder.limit_mut().max(8);
+    assert_eq!(builder.build().unwrap().limit, Limit { max: 8 });
+}
@@ -0,0 +1,65 @@
+// With #[builder(no_std)] the generated code only refers to `core` and `alloc`,
+// so the derive can be used in crates without `std`. `alloc` is still needed
+// for the `String` validation errors and has to be declared by the crate.
+//
+// This test is a `no_std` crate that only links `std` for running `main`, any
+// leftover `::std` path in the expansion fails to resolve.
+
+#![no_std]
+
+extern crate alloc;
+extern crate std as _;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+#[builder(no_std, validate = "Packet::validate")]
+pub struct Packet {
+    id: u16,
+    #[builder(each = "byte")]
+    payload: Vec<u8>,
+    checksum: Option<u8>,
+    #[builder(sub_builder)]
+    header: Header,
+}
+
+#[derive(Builder, Debug)]
+#[builder(no_std)]
+pub struct Header {
+    version: u8,
+}
+
+impl Packet {
+    fn validate(&self) -> Result<(), String> {
+        if self.payload.is_empty() {
+            return Err("empty payload".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Builder)]
+#[builder(no_std, typestate)]
+pub struct Frame {
+    length: usize,
+}
+
+fn main() {
+    let mut builder = Packet::builder();
+    builder.id(7).byte(1).byte(2);
+    builder.header_mut().version(1);
+    let packet = builder.build().unwrap();
+    assert_eq!(packet.payload, [1, 2]);
+    assert_eq!(packet.checksum, None);
+    assert_eq!(packet.header.version, 1);
+
+    let err = Packet::builder().id(1).build().unwrap_err();
+    assert_eq!(err.to_string(), "missing field `header.version`");
+
+    let err = Packet::builder().id(1).header(Header { version: 2 }).build().unwrap_err();
+    assert_eq!(err, PacketBuilderError::Validation("empty payload".to_string()));
+
+    assert_eq!(Frame::builder().length(64).build().length, 64);
+}
@@ -0,0 +1,48 @@
+// A facade crate can re-export the derive, so that its users don't have to
+// depend on derive_builder themselves. #[builder(crate = "...")] names where
+// derive_builder is reachable from and is checked to resolve.
+//
+// Everything the expansion refers to is spelled out from the crate root
+// (`::core::option::Option`, ...), so local modules named `core` or `std`
+// don't get in the way.
+
+mod sdk {
+    pub use derive_builder;
+}
+
+mod core {}
+mod std {}
+
+#[derive(sdk::derive_builder::Builder)]
+#[builder(crate = "sdk::derive_builder", validate = "Command::validate")]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+impl Command {
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[derive(sdk::derive_builder::Builder)]
+#[builder(crate = "crate::sdk::derive_builder", typestate)]
+pub enum Shape {
+    Circle { radius: f64 },
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.args, vec!["build"]);
+    assert!(command.current_dir.is_none());
+
+    let Shape::Circle { radius } = ShapeBuilder::circle().radius(1.0).build();
+    assert_eq!(radius, 1.0);
+}
@@ -0,0 +1,121 @@
+// A sub-builder is built by its parent whatever it looks like on its own: its
+// build method may be renamed, it may use the owned pattern, or build may be
+// async. The parent only relies on a hidden `__build_nested(&self)` that every
+// runtime checked builder has. Typestate builders can't be sub-builders, see
+// 27-sub-builder-typestate.rs.
+//
+// A parent using the immutable pattern clones itself in every setter, so the
+// builders of its sub-builder fields have to be `Clone` too.
+//
+// Synchronous `validate` functions of sub-builders run as part of the parent's
+// build. An `async_validate` can't be awaited there, so it only runs when the
+// sub-builder is built on its own.
+//
+// `merge` merges sub-builders set on both sides, so layered configs can each
+// set a few nested fields.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(build_fn(name = "finish"))]
+pub struct Tls {
+    cert: String,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(pattern = "owned", validate = "Pool::validate")]
+pub struct Pool {
+    size: u32,
+}
+
+impl Pool {
+    fn validate(&self) -> Result<(), String> {
+        if self.size == 0 {
+            return Err("size must not be 0".to_owned());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(build_fn(async_validate = "check_dns"))]
+pub struct Dns {
+    resolver: String,
+}
+
+async fn check_dns(_: &Dns) -> Result<(), String> {
+    Ok(())
+}
+
+#[derive(Builder, Debug, PartialEq)]
+pub struct Server {
+    host: String,
+    port: u16,
+    #[builder(sub_builder)]
+    tls: Tls,
+    #[builder(sub_builder)]
+    pool: Pool,
+    #[builder(sub_builder)]
+    dns: Dns,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(derive(Clone))]
+pub struct Upstream {
+    addr: String,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(pattern = "immutable")]
+pub struct Proxy {
+    name: String,
+    #[builder(sub_builder)]
+    upstream: Upstream,
+}
+
+fn main() {
+    let mut builder = Server::builder();
+    builder.host("localhost".to_owned()).port(443);
+    builder.tls_mut().cert("cert.pem".to_owned());
+    builder.dns_mut().resolver("1.1.1.1".to_owned());
+
+    let err = builder.build().unwrap_err();
+    assert_eq!(err, ServerBuilderError::MissingNestedField("pool.size".to_owned()));
+
+    // The owned setter takes the builder by value, so it's replaced as a whole.
+    *builder.pool_mut() = Pool::builder().size(0);
+    let err = builder.build().unwrap_err();
+    assert_eq!(err.to_string(), "invalid field `pool`: size must not be 0");
+
+    *builder.pool_mut() = Pool::builder().size(4);
+    let server = builder.build().unwrap();
+    assert_eq!(server.tls, Tls { cert: "cert.pem".to_owned() });
+    assert_eq!(server.pool, Pool { size: 4 });
+    assert_eq!(server.dns.resolver, "1.1.1.1");
+
+    // Layers setting different fields of the same sub-builder.
+    let mut defaults = Server::builder();
+    defaults.host("0.0.0.0".to_owned());
+    defaults.tls_mut().cert("default.pem".to_owned());
+    defaults.dns_mut().resolver("8.8.8.8".to_owned());
+    *defaults.pool_mut() = Pool::builder().size(1);
+
+    let mut user = Server::builder();
+    user.port(8080);
+    user.tls_mut().cert("user.pem".to_owned());
+    *user.pool_mut() = Pool::builder();
+
+    defaults.merge(user);
+    let server = defaults.build().unwrap();
+    assert_eq!(server.host, "0.0.0.0");
+    assert_eq!(server.port, 8080);
+    assert_eq!(server.tls.cert, "user.pem");
+    // The user's pool builder sets nothing, the default size is kept.
+    assert_eq!(server.pool.size, 1);
+    assert_eq!(server.dns.resolver, "8.8.8.8");
+
+    let mut builder = Proxy::builder().name("edge".to_owned());
+    builder.upstream_mut().addr("10.0.0.1".to_owned());
+    let proxy = builder.name("edge-1".to_owned()).build().unwrap();
+    assert_eq!(proxy.upstream, Upstream { addr: "10.0.0.1".to_owned() });
+}
@@ -0,0 +1,21 @@
+// A typestate builder changes its type with every required field that's set,
+// so it can't be handed out through `<field>_mut` and filled in place. Marking
+// a field whose type uses a typestate builder as `sub_builder` fails to
+// compile with a message saying so.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Tls {
+    cert: String,
+}
+
+#[derive(Builder)]
+pub struct Server {
+    host: String,
+    #[builder(sub_builder)]
+    tls: Tls,
+}
+
+fn main() {}
@@ -1,13 +1,31 @@
 #[test]
 fn tests() {
     let t = trybuild::TestCases::new();
-    //t.pass("tests/01-parse.rs");
-    //t.pass("tests/02-create-builder.rs");
-    //t.pass("tests/03-call-setters.rs");
-    //t.pass("tests/04-call-build.rs");
-    //t.pass("tests/05-method-chaining.rs");
-    //t.pass("tests/06-optional-field.rs");
-    //t.pass("tests/07-repeated-field.rs");
-    //t.compile_fail("tests/08-unrecognized-attribute.rs");
-    //t.pass("tests/09-redefined-prelude-types.rs");
+    t.pass("tests/01-parse.rs");
+    t.pass("tests/02-create-builder.rs");
+    t.pass("tests/03-call-setters.rs");
+    t.pass("tests/04-call-build.rs");
+    t.pass("tests/05-method-chaining.rs");
+    t.pass("tests/06-optional-field.rs");
+    t.pass("tests/07-repeated-field.rs");
+    t.compile_fail("tests/08-unrecognized-attribute.rs");
+    t.pass("tests/09-redefined-prelude-types.rs");
+    t.pass("tests/10-typestate.rs");
+    t.compile_fail("tests/11-typestate-missing-field.rs");
+    t.pass("tests/12-default-values.rs");
+    t.pass("tests/13-validate.rs");
+    t.pass("tests/14-generics.rs");
+    t.pass("tests/15-setter-options.rs");
+    t.pass("tests/16-each-collections.rs");
+    t.pass("tests/17-builder-patterns.rs");
+    t.pass("tests/18-enum-variants.rs");
+    t.pass("tests/19-try-setter-async-build.rs");
+    t.pass("tests/20-derive-and-merge.rs");
+    t.compile_fail("tests/21-attribute-diagnostics.rs");
+    t.pass("tests/22-to-builder.rs");
+    t.pass("tests/23-sub-builder.rs");
+    t.pass("tests/24-no-std.rs");
+    t.pass("tests/25-crate-path.rs");
+    t.pass("tests/26-sub-builder-patterns.rs");
+    t.compile_fail("tests/27-sub-builder-typestate.rs");
 }
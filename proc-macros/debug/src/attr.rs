@@ -0,0 +1,209 @@
+//! Parsing of the inert `#[debug ...]` attributes.
+
+use quote::ToTokens;
+use syn::meta::ParseNestedMeta;
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Expr, ExprLit, Lit, LitInt, LitStr, Meta, Path, Token, WherePredicate};
+
+/// Options given through `#[debug(...)]` on the struct or enum.
+#[derive(Default)]
+pub struct ContainerOpts {
+    /// Replaces the inferred where clause.
+    pub bound: Option<Vec<WherePredicate>>,
+    /// Stays on a single line under `{:#?}`.
+    pub compact: bool,
+    /// Ends with `..` if any fields are skipped.
+    pub non_exhaustive: bool,
+    /// Generates `debug_fields`, listing the printed fields.
+    pub fields: bool,
+}
+
+impl ContainerOpts {
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut opts = Self::default();
+
+        for attr in debug_attrs(attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("bound") {
+                    opts.bound = Some(parse_bound(&meta)?);
+                    Ok(())
+                } else if meta.path.is_ident("compact") {
+                    opts.compact = true;
+                    Ok(())
+                } else if meta.path.is_ident("non_exhaustive") {
+                    opts.non_exhaustive = true;
+                    Ok(())
+                } else if meta.path.is_ident("fields") {
+                    opts.fields = true;
+                    Ok(())
+                } else {
+                    Err(meta.error(
+                        "expected one of `debug(bound = \"...\")`, `debug(compact)`, `debug(non_exhaustive)` \
+                         or `debug(fields)`",
+                    ))
+                }
+            })?;
+        }
+
+        Ok(opts)
+    }
+}
+
+/// Options given through `#[debug(...)]` on an enum variant.
+#[derive(Default)]
+pub struct VariantOpts {
+    /// Replaces the bounds inferred from the variant's fields.
+    pub bound: Option<Vec<WherePredicate>>,
+}
+
+impl VariantOpts {
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut opts = Self::default();
+
+        for attr in debug_attrs(attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("bound") {
+                    opts.bound = Some(parse_bound(&meta)?);
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `debug(bound = \"...\")`"))
+                }
+            })?;
+        }
+
+        Ok(opts)
+    }
+}
+
+/// Options given through `#[debug ...]` on a single field.
+#[derive(Default)]
+pub struct FieldOpts {
+    /// Leaves the field out of the output.
+    pub skip: bool,
+    /// How the field is printed instead of through its `Debug` impl.
+    pub format: Option<FieldFormat>,
+    /// Replaces the bounds inferred from the field's type.
+    pub bound: Option<Vec<WherePredicate>>,
+    /// The name printed instead of the field's own.
+    pub rename: Option<LitStr>,
+    /// Prints the fields of the field's type in place of the field.
+    pub flatten: bool,
+}
+
+/// A replacement for a field's `Debug` impl.
+pub enum FieldFormat {
+    /// `#[debug = "..."]`, a format string applied to the field.
+    Str(LitStr),
+    /// `#[debug(with = "path")]`, a `fn(&T, &mut Formatter) -> fmt::Result`.
+    With(Path),
+    /// `#[debug(redact)]` prints `<redacted>`, `#[debug(redact = "len")]` adds the field's `len()`.
+    Redact { len: bool },
+    /// `#[debug(max_items = N)]`, prints the first `N` items of a collection and how many are left.
+    Truncate(usize),
+}
+
+impl FieldOpts {
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut opts = Self::default();
+
+        for attr in debug_attrs(attrs) {
+            match &attr.meta {
+                Meta::NameValue(meta) => match &meta.value {
+                    Expr::Lit(ExprLit { lit: Lit::Str(format), .. }) => {
+                        opts.set_format(attr, FieldFormat::Str(format.clone()))?;
+                    }
+                    value => return Err(syn::Error::new_spanned(value, "expected a format string")),
+                },
+                _ => attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("bound") {
+                        opts.bound = Some(parse_bound(&meta)?);
+                        Ok(())
+                    } else if meta.path.is_ident("skip") {
+                        opts.skip = true;
+                        Ok(())
+                    } else if meta.path.is_ident("rename") {
+                        opts.rename = Some(meta.value()?.parse()?);
+                        Ok(())
+                    } else if meta.path.is_ident("flatten") {
+                        opts.flatten = true;
+                        Ok(())
+                    } else if meta.path.is_ident("with") {
+                        let path: LitStr = meta.value()?.parse()?;
+                        opts.set_format(&meta.path, FieldFormat::With(path.parse()?))
+                    } else if meta.path.is_ident("redact") {
+                        let len = if meta.input.peek(Token![=]) {
+                            let summary: LitStr = meta.value()?.parse()?;
+                            if summary.value() != "len" {
+                                return Err(syn::Error::new_spanned(summary, "expected `redact = \"len\"`"));
+                            }
+                            true
+                        } else {
+                            false
+                        };
+                        opts.set_format(&meta.path, FieldFormat::Redact { len })
+                    } else if meta.path.is_ident("max_items") {
+                        let max: LitInt = meta.value()?.parse()?;
+                        opts.set_format(&meta.path, FieldFormat::Truncate(max.base10_parse()?))
+                    } else {
+                        Err(meta.error(
+                            "expected one of `debug = \"...\"`, `debug(skip)`, `debug(redact)`, \
+                             `debug(with = \"...\")`, `debug(max_items = N)`, `debug(rename = \"...\")`, \
+                             `debug(flatten)` or `debug(bound = \"...\")`",
+                        ))
+                    }
+                })?,
+            }
+        }
+
+        if let (true, Some(_)) = (opts.skip, &opts.format) {
+            let attr = debug_attrs(attrs).next().expect("skip comes from an attribute");
+            return Err(syn::Error::new_spanned(attr, "skipped fields can't have a format"));
+        }
+        if opts.flatten && (opts.skip || opts.format.is_some() || opts.rename.is_some()) {
+            let attr = debug_attrs(attrs).next().expect("flatten comes from an attribute");
+            return Err(syn::Error::new_spanned(
+                attr,
+                "flattened fields print their type's fields, they can't be skipped, renamed or formatted",
+            ));
+        }
+
+        Ok(opts)
+    }
+
+    fn set_format(&mut self, tokens: impl ToTokens, format: FieldFormat) -> syn::Result<()> {
+        if self.format.is_some() {
+            return Err(syn::Error::new_spanned(
+                tokens,
+                "only one of `debug = \"...\"`, `debug(with = \"...\")`, `debug(redact)` and \
+                 `debug(max_items = N)` can be used",
+            ));
+        }
+        self.format = Some(format);
+        Ok(())
+    }
+}
+
+/// The template of `#[display("...")]`, on the container or a variant.
+pub fn display_template(attrs: &[Attribute]) -> syn::Result<Option<LitStr>> {
+    let mut template = None;
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("display")) {
+        if template.is_some() {
+            return Err(syn::Error::new_spanned(attr, "duplicate `#[display(\"...\")]`"));
+        }
+        template = Some(attr.parse_args::<LitStr>()?);
+    }
+
+    Ok(template)
+}
+
+/// Parses `bound = "T: Trait, U::Item: Trait"`, an empty string removes all bounds.
+fn parse_bound(meta: &ParseNestedMeta) -> syn::Result<Vec<WherePredicate>> {
+    let bound: LitStr = meta.value()?.parse()?;
+    let predicates = bound.parse_with(Punctuated::<WherePredicate, Token![,]>::parse_terminated)?;
+    Ok(predicates.into_iter().collect())
+}
+
+fn debug_attrs(attrs: &[Attribute]) -> impl Iterator<Item=&Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("debug"))
+}
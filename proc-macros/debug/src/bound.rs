@@ -0,0 +1,199 @@
+//! Inferring which bounds the generated impl needs.
+//!
+//! Bounding every field type (`Vec<T>: Debug`) breaks on recursive types and leaks private
+//! types into the impl's signature, so bounds are put on the type parameters instead, and
+//! only on those a field actually needs formatted. Where a parameter is hidden behind
+//! something the impls of which can't be seen through, like `<T as Trait>::Value` or
+//! `dyn Trait<T>`, that whole type is bounded instead.
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::visit::{self, Visit};
+use syn::{parse_quote, Generics, Ident, Path, Type, TypeMacro, TypeParamBound, TypePath, WherePredicate};
+
+/// Where the bounds of the impl come from.
+pub enum Bounded<'a> {
+    /// A field type, the bounds of which are inferred.
+    Type(&'a Type),
+    /// Given by `bound = "..."` on a field or variant, replacing whatever would be inferred.
+    Given(&'a [WherePredicate]),
+}
+
+/// The where clause predicates needed to format all `fields` with `trait_path`, without
+/// repeats.
+///
+/// Fails for types that can't be looked into, suggesting the `bound` option of `attr` if the
+/// derive has one.
+pub fn infer<'a>(
+    generics: &Generics,
+    fields: impl IntoIterator<Item=Bounded<'a>>,
+    trait_path: &Path,
+    attr: Option<&str>,
+) -> syn::Result<Vec<WherePredicate>> {
+    let mut visitor = ParamVisitor {
+        params: generics.type_params().map(|param| &param.ident).collect(),
+        bounded: Vec::new(),
+        undecidable: None,
+    };
+    let mut predicates = Vec::new();
+
+    for field in fields {
+        match field {
+            Bounded::Type(ty) => visitor.visit_type(ty),
+            Bounded::Given(bound) => predicates.extend(bound.iter().cloned()),
+        }
+    }
+    if let Some(mac) = visitor.undecidable {
+        let message = match attr {
+            Some(attr) => format!(
+                "can't infer which bounds this type needs, give them with `#[{}(bound = \"...\")]` \
+                 on the field, variant or type",
+                attr,
+            ),
+            None => "can't infer which bounds this type needs".to_owned(),
+        };
+        return Err(syn::Error::new_spanned(mac, message));
+    }
+    predicates.extend(visitor.bounded.iter().map(|ty| -> WherePredicate { parse_quote!(#ty: #trait_path) }));
+
+    // Bounds the type already declares don't need repeating.
+    let declared = declared(generics);
+    predicates.retain(|predicate| {
+        let WherePredicate::Type(predicate) = predicate else { return true };
+        let ty = predicate.bounded_ty.to_token_stream().to_string();
+        !predicate.bounds.iter().all(|bound| {
+            declared.iter().any(|(declared_ty, declared)| *declared_ty == ty && same_trait(declared, bound))
+        })
+    });
+
+    Ok(dedup(predicates))
+}
+
+/// Every bound written on the type, with the type it bounds as a string for comparing.
+fn declared(generics: &Generics) -> Vec<(String, &TypeParamBound)> {
+    let params = generics.type_params().map(|param| (param.ident.to_string(), &param.bounds));
+    let predicates = generics
+        .where_clause
+        .iter()
+        .flat_map(|where_clause| &where_clause.predicates)
+        .filter_map(|predicate| match predicate {
+            WherePredicate::Type(predicate) => {
+                Some((predicate.bounded_ty.to_token_stream().to_string(), &predicate.bounds))
+            }
+            _ => None,
+        });
+
+    params
+        .chain(predicates)
+        .flat_map(|(ty, bounds)| bounds.iter().map(move |bound| (ty.clone(), bound)))
+        .collect()
+}
+
+/// Whether two trait bounds name the same trait, taking `Debug`, `fmt::Debug` and
+/// `std::fmt::Debug` all to mean `::core::fmt::Debug`.
+fn same_trait(a: &TypeParamBound, b: &TypeParamBound) -> bool {
+    fn segments(bound: &TypeParamBound) -> Option<Vec<String>> {
+        let TypeParamBound::Trait(bound) = bound else { return None };
+        let segments = bound.path.segments.iter().map(|segment| match segment.ident.to_string() {
+            ident if ident == "std" => "core".to_owned(),
+            ident => ident + &segment.arguments.to_token_stream().to_string(),
+        });
+        Some(segments.collect())
+    }
+
+    match (segments(a), segments(b)) {
+        (Some(a), Some(b)) => a.ends_with(&b) || b.ends_with(&a),
+        _ => false,
+    }
+}
+
+/// Removes repeated predicates, keeping the first of each. A bound repeated with a different
+/// path to the same trait, like `T: Debug` given on one variant and `T: ::core::fmt::Debug`
+/// inferred for another, counts as repeated too.
+fn dedup(predicates: Vec<WherePredicate>) -> Vec<WherePredicate> {
+    let mut seen = Vec::new();
+    let mut bounds_seen: Vec<(String, TypeParamBound)> = Vec::new();
+    predicates
+        .into_iter()
+        .filter_map(|mut predicate| {
+            if let WherePredicate::Type(predicate) = &mut predicate {
+                let ty = predicate.bounded_ty.to_token_stream().to_string();
+                let bounds = std::mem::take(&mut predicate.bounds);
+                for bound in bounds {
+                    if !bounds_seen.iter().any(|(seen_ty, seen)| *seen_ty == ty && same_trait(seen, &bound)) {
+                        bounds_seen.push((ty.clone(), bound.clone()));
+                        predicate.bounds.push(bound);
+                    }
+                }
+                if predicate.bounds.is_empty() {
+                    return None;
+                }
+            }
+            let tokens = predicate.to_token_stream().to_string();
+            let new = !seen.contains(&tokens);
+            seen.push(tokens);
+            new.then_some(predicate)
+        })
+        .collect()
+}
+
+/// Collects the types that need bounds because they mention type parameters.
+struct ParamVisitor<'a> {
+    params: Vec<&'a Ident>,
+    bounded: Vec<Type>,
+    /// The first type macro mentioning a parameter, which can't be looked into.
+    undecidable: Option<TypeMacro>,
+}
+
+impl ParamVisitor<'_> {
+    fn mentions_param(&self, tokens: TokenStream) -> bool {
+        tokens.into_iter().any(|token| match token {
+            TokenTree::Ident(ident) => self.params.iter().any(|param| **param == ident),
+            TokenTree::Group(group) => self.mentions_param(group.stream()),
+            _ => false,
+        })
+    }
+}
+
+impl<'ast> Visit<'ast> for ParamVisitor<'_> {
+    fn visit_type(&mut self, ty: &'ast Type) {
+        match ty {
+            // Implement `Debug` whatever they point to or take.
+            Type::BareFn(_) | Type::Ptr(_) => {}
+            // Whether `dyn Trait<T>` is formattable depends on `Trait`, not on `T`.
+            Type::TraitObject(_) if self.mentions_param(ty.to_token_stream()) => self.bounded.push(ty.clone()),
+            // A bare `dyn Trait<T>` would mean `dyn Trait<T> + 'static`, the reference keeps its lifetime.
+            Type::Reference(reference)
+                if matches!(*reference.elem, Type::TraitObject(_)) && self.mentions_param(ty.to_token_stream()) =>
+            {
+                self.bounded.push(ty.clone());
+            }
+            Type::Macro(mac) if self.undecidable.is_none() && self.mentions_param(mac.mac.tokens.clone()) => {
+                self.undecidable = Some(mac.clone());
+            }
+            _ => visit::visit_type(self, ty),
+        }
+    }
+
+    fn visit_type_path(&mut self, ty: &'ast TypePath) {
+        // `<T as Trait>::Value` is whatever the impl of `Trait` says, only the whole type can be bounded.
+        if ty.qself.is_some() {
+            if self.mentions_param(ty.to_token_stream()) {
+                self.bounded.push(Type::Path(ty.clone()));
+            }
+            return;
+        }
+
+        let segments = &ty.path.segments;
+        // `T` or `T::Value`, the latter doesn't need `T` itself to be formattable.
+        if self.params.iter().any(|param| *param == &segments[0].ident) {
+            self.bounded.push(Type::Path(ty.clone()));
+            return;
+        }
+        if segments.last().is_some_and(|segment| segment.ident == "PhantomData") {
+            return;
+        }
+
+        visit::visit_type_path(self, ty);
+    }
+}
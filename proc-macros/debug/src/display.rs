@@ -0,0 +1,180 @@
+//! Code generation for `#[derive(CustomDisplay)]`.
+
+use std::collections::BTreeMap;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{Data, DeriveInput, Fields, Ident, LitStr, Path, Type};
+
+use crate::attr;
+use crate::bound::{self, Bounded};
+use crate::template::{self, Arg};
+
+pub fn derive(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+    let template = attr::display_template(&input.attrs)?;
+
+    let arms = match &input.data {
+        Data::Struct(data) => {
+            let template = template.as_ref().ok_or_else(|| {
+                syn::Error::new_spanned(ident, "`CustomDisplay` requires a `#[display(\"...\")]` template")
+            })?;
+            vec![Arm::new(quote!(Self), ident, &data.fields, Some(template))?]
+        }
+        // A template on the enum is used for all variants without their own.
+        Data::Enum(data) => data
+            .variants
+            .iter()
+            .map(|variant| {
+                let variant_ident = &variant.ident;
+                let own = attr::display_template(&variant.attrs)?;
+                let template = own.as_ref().or(template.as_ref());
+                Arm::new(quote!(Self::#variant_ident), variant_ident, &variant.fields, template)
+            })
+            .collect::<syn::Result<_>>()?,
+        Data::Union(_) => return Err(syn::Error::new_spanned(ident, "`CustomDisplay` can't be derived for unions")),
+    };
+
+    // Each field is bounded by the traits its placeholders format it with.
+    let mut by_trait = BTreeMap::<&str, Vec<&Type>>::new();
+    for (ty, trait_name) in arms.iter().flat_map(|arm| &arm.bounded) {
+        by_trait.entry(trait_name).or_default().push(ty);
+    }
+    let mut generics = input.generics.clone();
+    for (trait_name, types) in by_trait {
+        let trait_ident = format_ident!("{}", trait_name);
+        let trait_path: Path = syn::parse_quote!(::core::fmt::#trait_ident);
+        let fields = types.into_iter().map(Bounded::Type);
+        let predicates = bound::infer(&input.generics, fields, &trait_path, None)?;
+        generics.make_where_clause().predicates.extend(predicates);
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let arms = arms.iter().map(|arm| &arm.tokens);
+
+    Ok(quote! {
+        impl #impl_generics ::core::fmt::Display for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                match *self {
+                    #(#arms)*
+                }
+            }
+        }
+    })
+}
+
+/// The match arm formatting a struct or one enum variant.
+struct Arm<'a> {
+    tokens: TokenStream,
+    /// Every field's type with the trait it has to implement.
+    bounded: Vec<(&'a Type, &'static str)>,
+}
+
+impl<'a> Arm<'a> {
+    fn new(path: TokenStream, ident: &Ident, fields: &'a Fields, template: Option<&LitStr>) -> syn::Result<Self> {
+        let Some(template) = template else {
+            if let Fields::Unit = fields {
+                let name = ident.unraw().to_string();
+                return Ok(Self { tokens: quote! { #path => f.write_str(#name), }, bounded: Vec::new() });
+            }
+            return Err(syn::Error::new_spanned(ident, "missing `#[display(\"...\")]` template"));
+        };
+
+        let fields: Vec<_> = fields.iter().collect();
+        let mut used = vec![false; fields.len()];
+        let mut counted = vec![false; fields.len()];
+        let mut pointed = vec![false; fields.len()];
+        let parsed = template::parse(&template.value(), |arg, trait_name| {
+            let index = field_index(ident, &fields, arg)?;
+            if trait_name.is_none() {
+                counted[index] = true;
+                Ok(format!("__count_{}", index))
+            } else if trait_name == Some("Pointer") {
+                pointed[index] = true;
+                Ok(format!("__pointer_{}", index))
+            } else {
+                used[index] = true;
+                Ok(format!("__self_{}", index))
+            }
+        })
+        .map_err(|message| syn::Error::new_spanned(template, message))?;
+
+        let bounded = parsed
+            .uses
+            .iter()
+            .filter_map(|usage| {
+                let index = field_index(ident, &fields, &usage.arg).ok()?;
+                Some((&fields[index].ty, usage.trait_name?))
+            })
+            .collect();
+
+        let patterns = (0..fields.len()).map(|i| {
+            let binding = format_ident!("__self_{}", i);
+            if used[i] || counted[i] || pointed[i] {
+                quote!(ref #binding)
+            } else {
+                quote!(_)
+            }
+        });
+        let pattern = match fields.first().and_then(|field| field.ident.as_ref()) {
+            Some(_) => {
+                let idents = fields.iter().map(|field| &field.ident);
+                quote! { #path { #(#idents: #patterns),* } }
+            }
+            None if fields.is_empty() => quote! { #path { .. } },
+            None => quote! { #path(#(#patterns),*) },
+        };
+        let values = (0..fields.len()).filter(|i| used[*i]).map(|i| {
+            let binding = format_ident!("__self_{}", i);
+            quote! { #binding = #binding }
+        });
+        let counts = (0..fields.len()).filter(|i| counted[*i]).map(|i| {
+            let binding = format_ident!("__self_{}", i);
+            let count = format_ident!("__count_{}", i);
+            quote! { #count = *#binding }
+        });
+        // The fields are bound by reference, `{:p}` would print where the field itself is.
+        // `__Pointer` formats the pointer stored in it instead.
+        let pointers = (0..fields.len()).filter(|i| pointed[*i]).map(|i| {
+            let binding = format_ident!("__self_{}", i);
+            let pointer = format_ident!("__pointer_{}", i);
+            quote! { #pointer = __Pointer(#binding) }
+        });
+        let pointer_ty = pointed.contains(&true).then(|| quote! {
+            struct __Pointer<'__a, T: ?::core::marker::Sized>(&'__a T);
+
+            impl<T: ::core::fmt::Pointer + ?::core::marker::Sized> ::core::fmt::Pointer for __Pointer<'_, T> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    ::core::fmt::Pointer::fmt(self.0, f)
+                }
+            }
+        });
+        let format = LitStr::new(&parsed.format, template.span());
+
+        Ok(Self {
+            tokens: quote! {
+                #pattern => {
+                    #pointer_ty
+                    ::core::write!(f, #format #(, #values)* #(, #pointers)* #(, #counts)*)
+                }
+            },
+            bounded,
+        })
+    }
+}
+
+/// The position of the field `arg` refers to, or why there is none.
+fn field_index(ident: &Ident, fields: &[&syn::Field], arg: &Arg) -> Result<usize, String> {
+    let named = fields.first().is_some_and(|field| field.ident.is_some());
+
+    match arg {
+        Arg::Named(name) => fields
+            .iter()
+            .position(|field| field.ident.as_ref().is_some_and(|field| field.unraw() == name))
+            .ok_or_else(|| format!("no field `{}` in `{}`", name, ident)),
+        Arg::Index(_) if named => Err(format!("refer to the fields of `{}` by name", ident)),
+        Arg::Index(index) if *index < fields.len() => Ok(*index),
+        Arg::Index(index) => Err(format!("no field `{}` in `{}`, it has {} fields", index, ident, fields.len())),
+    }
+}
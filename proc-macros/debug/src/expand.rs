@@ -0,0 +1,484 @@
+//! Code generation for `#[derive(CustomDebug)]`.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{parse_quote, Data, DeriveInput, Fields, Generics, Ident, Path, Type};
+
+use crate::attr::{ContainerOpts, FieldFormat, FieldOpts, VariantOpts};
+use crate::bound::{self, Bounded};
+
+pub fn derive(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let opts = ContainerOpts::from_attrs(&input.attrs)?;
+    let ident = &input.ident;
+
+    let shapes = match &input.data {
+        Data::Struct(data) => vec![Shape::new(quote!(Self), ident, &data.fields, VariantOpts::default())?],
+        Data::Enum(data) => data
+            .variants
+            .iter()
+            .map(|variant| {
+                let variant_ident = &variant.ident;
+                let opts = VariantOpts::from_attrs(&variant.attrs)?;
+                Shape::new(quote!(Self::#variant_ident), variant_ident, &variant.fields, opts)
+            })
+            .collect::<syn::Result<_>>()?,
+        Data::Union(_) => return Err(syn::Error::new_spanned(ident, "`CustomDebug` can't be derived for unions")),
+    };
+
+    let debug: Path = parse_quote!(::core::fmt::Debug);
+    let mut generics = input.generics.clone();
+    let predicates = match &opts.bound {
+        Some(bound) => bound.clone(),
+        None => bound::infer(&input.generics, shapes.iter().flat_map(Shape::bounded), &debug, Some("debug"))?,
+    };
+    generics.make_where_clause().predicates.extend(predicates);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let arms = shapes.iter().map(|shape| shape.arm(&opts));
+    let helpers = helpers(&shapes);
+    // Formatting `self` again without `#` ends up in the arms below, on a single line.
+    let compact = opts.compact.then(|| quote! {
+        if f.alternate() {
+            return ::core::write!(f, "{:?}", self);
+        }
+    });
+
+    // Structs with named fields can be flattened into other structs' output.
+    let flatten = match (&input.data, shapes.first()) {
+        (Data::Struct(_), Some(shape @ Shape { kind: ShapeKind::Named, .. })) => {
+            let path = &shape.path;
+            let idents = shape.fields.iter().map(|field| field.ident);
+            let patterns = shape.fields.iter().map(Field::pattern);
+            let fields = shape.named_fields();
+            Some(quote! {
+                impl #impl_generics #ident #ty_generics #where_clause {
+                    /// Adds the fields to the output of a struct that has this one as a
+                    /// `#[debug(flatten)]` field.
+                    #[doc(hidden)]
+                    #[allow(dead_code)]
+                    pub fn __debug_flatten(&self, __debug: &mut ::core::fmt::DebugStruct<'_, '_>) {
+                        #helpers
+
+                        match *self {
+                            #path { #(#idents: #patterns),* } => {
+                                #(#fields)*
+                            }
+                        }
+                    }
+                }
+            })
+        }
+        _ => None,
+    };
+
+    let debug_fields = match opts.fields {
+        true => Some(debug_fields(input, &shapes, &generics, &helpers)?),
+        false => None,
+    };
+
+    Ok(quote! {
+        #flatten
+        #debug_fields
+
+        impl #impl_generics #debug for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                #helpers
+                #compact
+
+                match *self {
+                    #(#arms)*
+                }
+            }
+        }
+    })
+}
+
+/// The inherent `debug_fields` method of `#[debug(fields)]`, listing the printed fields with
+/// the values they're printed as.
+fn debug_fields(input: &DeriveInput, shapes: &[Shape], generics: &Generics, helpers: &TokenStream) -> syn::Result<TokenStream> {
+    let DeriveInput { vis, ident, .. } = input;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let debug = quote!(::core::fmt::Debug);
+
+    if let Some(field) = shapes.iter().flat_map(|shape| &shape.fields).find(|field| field.opts.flatten) {
+        return Err(syn::Error::new_spanned(field.field, "`debug(fields)` can't list the fields of flattened fields"));
+    }
+
+    // A formatted value only lives as long as the call formatting it, so each formatted field
+    // gets a wrapper around the whole value instead, which `&self` can be cast to.
+    let mut wrappers = Vec::new();
+    // Variants with fewer fields fill up the array with `None`.
+    let len = shapes.iter().map(|shape| shape.printed().count()).max().unwrap_or(0);
+    let arms = shapes.iter().map(|shape| {
+        let path = &shape.path;
+        // `Self` means the wrapper inside its impl.
+        let type_path = match input.data {
+            Data::Enum(_) => {
+                let variant = shape.ident;
+                quote!(#ident::#variant)
+            }
+            _ => quote!(#ident),
+        };
+        let mut patterns = Vec::new();
+        let mut fields = Vec::new();
+        for (i, field) in shape.fields.iter().enumerate() {
+            let binding = &field.binding;
+            let name = match shape.kind {
+                ShapeKind::Named => field.name(),
+                _ => i.to_string(),
+            };
+            match (&field.opts.format, field.opts.skip) {
+                (_, true) => patterns.push(quote!(_)),
+                (None, false) => {
+                    patterns.push(quote!(ref #binding));
+                    fields.push(quote! { ::core::option::Option::Some((#name, #binding as &dyn #debug)) });
+                }
+                (Some(_), false) => {
+                    patterns.push(quote!(_));
+                    let index = wrappers.len();
+                    let value = field.value();
+                    let pattern = match field.ident {
+                        Some(field_ident) => quote!(#type_path { #field_ident: ref #binding, .. }),
+                        None => {
+                            let before = (0..i).map(|_| quote!(_));
+                            quote!(#type_path(#(#before,)* ref #binding, ..))
+                        }
+                    };
+                    wrappers.push(quote! {
+                        impl #impl_generics #debug for __Field<#ident #ty_generics, #index> #where_clause {
+                            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                                #[allow(unreachable_patterns)]
+                                match self.0 {
+                                    #pattern => #debug::fmt(#value, f),
+                                    _ => ::core::unreachable!(),
+                                }
+                            }
+                        }
+                    });
+                    fields.push(quote! {
+                        // SAFETY: `__Field` is a `repr(transparent)` wrapper around `Self`.
+                        ::core::option::Option::Some((#name, unsafe {
+                            &*(self as *const Self as *const __Field<Self, #index>)
+                        } as &dyn #debug))
+                    });
+                }
+            }
+        }
+        fields.resize(len, quote!(::core::option::Option::None));
+
+        let pattern = match shape.kind {
+            ShapeKind::Named => {
+                let idents = shape.fields.iter().map(|field| field.ident);
+                quote!(#path { #(#idents: #patterns),* })
+            }
+            ShapeKind::Unnamed => quote!(#path(#(#patterns),*)),
+            ShapeKind::Unit => quote!(#path),
+        };
+        quote! { #pattern => [#(#fields),*], }
+    });
+    let arms: Vec<_> = arms.collect();
+    let wrapper = (!wrappers.is_empty()).then(|| quote! {
+        #[repr(transparent)]
+        struct __Field<T: ?::core::marker::Sized, const I: usize>(T);
+    });
+
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// The fields as printed by the `Debug` impl, as pairs of name and value. Tuple
+            /// fields are named by their index.
+            #vis fn debug_fields(&self) -> impl ::core::iter::Iterator<Item = (&'static str, &dyn #debug)> + '_ {
+                #helpers
+                #wrapper
+                #(#wrappers)*
+
+                let fields: [::core::option::Option<(&'static str, &dyn #debug)>; #len] = match *self {
+                    #(#arms)*
+                };
+                ::core::iter::Iterator::flatten(::core::iter::IntoIterator::into_iter(fields))
+            }
+        }
+    })
+}
+
+/// The wrappers used by the fields' formats, defined inside `fmt` so they can't clash with
+/// anything.
+fn helpers(shapes: &[Shape]) -> TokenStream {
+    let mut with = false;
+    let mut collections = Vec::new();
+    for field in shapes.iter().flat_map(|shape| &shape.fields) {
+        match field.opts.format {
+            Some(FieldFormat::With(_)) => with = true,
+            Some(FieldFormat::Truncate(_)) => {
+                let collection = Collection::of(&field.field.ty);
+                if !collections.contains(&collection) {
+                    collections.push(collection);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let with = with.then(|| quote! {
+        /// Formats a field through a `debug(with = "...")` function.
+        struct __DebugWith<'a, T: ?::core::marker::Sized>(
+            &'a T,
+            fn(&T, &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result,
+        );
+
+        impl<T: ?::core::marker::Sized> ::core::fmt::Debug for __DebugWith<'_, T> {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                (self.1)(self.0, f)
+            }
+        }
+    });
+    let truncated = collections.iter().map(|collection| {
+        let ident = collection.wrapper();
+        let (builder, item, entry) = match collection {
+            Collection::List => (quote!(debug_list), quote!(I), quote!(item)),
+            Collection::Set => (quote!(debug_set), quote!(I), quote!(item)),
+            // Maps are printed as a set of `key: value` entries, so the rest fits in as an entry.
+            Collection::Map => (quote!(debug_set), quote!((K, V)), quote!(__Entry(item.0, item.1))),
+        };
+        let params = match collection {
+            Collection::Map => quote!(K: ::core::fmt::Debug, V: ::core::fmt::Debug),
+            _ => quote!(I: ::core::fmt::Debug),
+        };
+
+        quote! {
+            /// Formats at most `.1` items of a `debug(max_items = N)` collection.
+            struct #ident<'a, T: ?::core::marker::Sized>(&'a T, usize);
+
+            impl<'a, T: ?::core::marker::Sized, #params> ::core::fmt::Debug for #ident<'a, T>
+            where
+                &'a T: ::core::iter::IntoIterator<Item = #item>,
+            {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut items = ::core::iter::IntoIterator::into_iter(self.0);
+                    let mut output = f.#builder();
+                    for item in ::core::iter::Iterator::take(::core::iter::Iterator::by_ref(&mut items), self.1) {
+                        output.entry(&#entry);
+                    }
+                    let rest = ::core::iter::Iterator::count(items);
+                    if rest > 0 {
+                        output.entry(&::core::format_args!(".. {} more", rest));
+                    }
+                    output.finish()
+                }
+            }
+        }
+    });
+    let entry = collections.contains(&Collection::Map).then(|| quote! {
+        /// A map entry, formatted as `key: value`.
+        struct __Entry<K, V>(K, V);
+
+        impl<K: ::core::fmt::Debug, V: ::core::fmt::Debug> ::core::fmt::Debug for __Entry<K, V> {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                ::core::fmt::Debug::fmt(&self.0, f)?;
+                f.write_str(": ")?;
+                ::core::fmt::Debug::fmt(&self.1, f)
+            }
+        }
+    });
+
+    quote! {
+        #with
+        #(#truncated)*
+        #entry
+    }
+}
+
+/// How a `debug(max_items = N)` collection is printed, going by its type's name.
+#[derive(PartialEq)]
+enum Collection {
+    /// `[a, b]`
+    List,
+    /// `{a, b}`
+    Set,
+    /// `{a: 1, b: 2}`
+    Map,
+}
+
+impl Collection {
+    fn of(ty: &Type) -> Self {
+        let name = match ty {
+            Type::Path(path) => path.path.segments.last().map(|segment| segment.ident.to_string()),
+            _ => None,
+        };
+        match name.as_deref() {
+            Some("HashMap" | "BTreeMap") => Collection::Map,
+            Some("HashSet" | "BTreeSet") => Collection::Set,
+            _ => Collection::List,
+        }
+    }
+
+    fn wrapper(&self) -> Ident {
+        match self {
+            Collection::List => format_ident!("__TruncatedList"),
+            Collection::Set => format_ident!("__TruncatedSet"),
+            Collection::Map => format_ident!("__TruncatedMap"),
+        }
+    }
+}
+
+/// A struct or one enum variant, formatted like `#[derive(Debug)]` would.
+struct Shape<'a> {
+    /// `Self` or `Self::Variant`.
+    path: TokenStream,
+    ident: &'a Ident,
+    kind: ShapeKind,
+    fields: Vec<Field<'a>>,
+    opts: VariantOpts,
+}
+
+enum ShapeKind {
+    Named,
+    Unnamed,
+    Unit,
+}
+
+struct Field<'a> {
+    /// The field's name, `None` for tuple fields.
+    ident: Option<&'a Ident>,
+    field: &'a syn::Field,
+    /// The variable the field is bound to in the match arm.
+    binding: Ident,
+    opts: FieldOpts,
+}
+
+impl<'a> Shape<'a> {
+    fn new(path: TokenStream, ident: &'a Ident, fields: &'a Fields, opts: VariantOpts) -> syn::Result<Self> {
+        let kind = match fields {
+            Fields::Named(_) => ShapeKind::Named,
+            Fields::Unnamed(_) => ShapeKind::Unnamed,
+            Fields::Unit => ShapeKind::Unit,
+        };
+        let fields = fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let opts = FieldOpts::from_attrs(&field.attrs)?;
+                if field.ident.is_none() && (opts.rename.is_some() || opts.flatten) {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "tuple fields are printed without a name, they can't be renamed or flattened",
+                    ));
+                }
+                Ok(Field { ident: field.ident.as_ref(), field, binding: format_ident!("__self_{}", i), opts })
+            })
+            .collect::<syn::Result<_>>()?;
+
+        Ok(Self { path, ident, kind, fields, opts })
+    }
+
+    /// The fields that need their type's `Debug` impl, or the bounds given explicitly for the
+    /// variant or a field.
+    fn bounded(&self) -> Vec<Bounded<'_>> {
+        if let Some(bound) = &self.opts.bound {
+            return vec![Bounded::Given(bound)];
+        }
+
+        self.fields
+            .iter()
+            .filter_map(|field| match (&field.opts.bound, &field.opts.format) {
+                (Some(bound), _) => Some(Bounded::Given(bound)),
+                (None, _) if field.opts.skip => None,
+                (None, None | Some(FieldFormat::Truncate(_))) => Some(Bounded::Type(&field.field.ty)),
+                (None, Some(_)) => None,
+            })
+            .collect()
+    }
+
+    fn printed(&self) -> impl Iterator<Item=&Field<'a>> {
+        self.fields.iter().filter(|field| !field.opts.skip)
+    }
+
+    /// Adds the printed named fields to `__debug`, a `DebugStruct`.
+    fn named_fields(&self) -> impl Iterator<Item=TokenStream> + '_ {
+        self.printed().map(|field| {
+            let binding = &field.binding;
+            if field.opts.flatten {
+                let ty = &field.field.ty;
+                return quote! { <#ty>::__debug_flatten(#binding, __debug); };
+            }
+            let name = field.name();
+            let value = field.value();
+            quote! { __debug.field(#name, #value); }
+        })
+    }
+
+    fn arm(&self, opts: &ContainerOpts) -> TokenStream {
+        let path = &self.path;
+        let finish = if opts.non_exhaustive && self.fields.iter().any(|field| field.opts.skip) {
+            quote!(finish_non_exhaustive)
+        } else {
+            quote!(finish)
+        };
+        let name = self.ident.unraw().to_string();
+        let patterns = self.fields.iter().map(Field::pattern);
+
+        match self.kind {
+            ShapeKind::Named => {
+                let idents = self.fields.iter().map(|field| field.ident);
+                let fields = self.named_fields();
+                quote! {
+                    #path { #(#idents: #patterns),* } => {
+                        let __debug = &mut f.debug_struct(#name);
+                        #(#fields)*
+                        __debug.#finish()
+                    }
+                }
+            }
+            ShapeKind::Unnamed => {
+                let fields = self.printed().map(|field| {
+                    let value = field.value();
+                    quote! { .field(#value) }
+                });
+                quote! {
+                    #path(#(#patterns),*) => f.debug_tuple(#name) #(#fields)* .#finish(),
+                }
+            }
+            ShapeKind::Unit => quote! {
+                #path => f.write_str(#name),
+            },
+        }
+    }
+}
+
+impl Field<'_> {
+    /// The name printed for a named field.
+    fn name(&self) -> String {
+        match &self.opts.rename {
+            Some(rename) => rename.value(),
+            None => self.ident.expect("named field").unraw().to_string(),
+        }
+    }
+
+    /// Binds the field in the match arm, unless it's never used.
+    fn pattern(&self) -> TokenStream {
+        let binding = &self.binding;
+        if self.opts.skip {
+            quote!(_)
+        } else {
+            quote!(ref #binding)
+        }
+    }
+
+    /// The `&dyn Debug` passed to the debug builder.
+    fn value(&self) -> TokenStream {
+        let binding = &self.binding;
+        match &self.opts.format {
+            Some(FieldFormat::Str(format)) => quote! { &::core::format_args!(#format, #binding) },
+            Some(FieldFormat::With(path)) => quote! { &__DebugWith(#binding, |value, f| #path(value, f)) },
+            Some(FieldFormat::Redact { len: false }) => quote! { &::core::format_args!("<redacted>") },
+            Some(FieldFormat::Redact { len: true }) => {
+                quote! { &::core::format_args!("<redacted, len {}>", #binding.len()) }
+            }
+            Some(FieldFormat::Truncate(max)) => {
+                let wrapper = Collection::of(&self.field.ty).wrapper();
+                quote! { &#wrapper(#binding, #max) }
+            }
+            None => quote! { #binding },
+        }
+    }
+}
@@ -1,8 +1,74 @@
 use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
 
-#[proc_macro_derive(CustomDebug)]
-pub fn derive(input: TokenStream) -> TokenStream {
-    let _ = input;
+mod attr;
+mod bound;
+mod display;
+mod expand;
+mod template;
 
-    unimplemented!()
+/// Derives `Debug` with the same output as `#[derive(Debug)]`, for structs of every kind and for
+/// enums.
+///
+/// Type parameters only get a `Debug` bound if a field needs it: parameters that only appear in
+/// `PhantomData`, function pointers or raw pointers get none, and for associated types like
+/// `T::Value` the bound is put on the associated type instead. `<T as Trait>::Value` and
+/// `dyn Trait<T>` are bounded as a whole. Fields whose type is a macro call need their bounds
+/// given explicitly.
+///
+/// Container attributes:
+/// - `#[debug(bound = "T::Value: Debug")]`: replaces all inferred bounds.
+/// - `#[debug(compact)]`: prints on a single line, even with `{:#?}`.
+/// - `#[debug(non_exhaustive)]`: ends with `..` when fields are skipped, like
+///   `finish_non_exhaustive`.
+/// - `#[debug(fields)]`: also generates
+///   `fn debug_fields(&self) -> impl Iterator<Item = (&'static str, &dyn Debug)>`, listing the
+///   printed fields with their names and values as the `Debug` impl prints them, for structured
+///   logging. Tuple fields are named by their index.
+///
+/// Variant attributes:
+/// - `#[debug(bound = "E: Debug")]`: replaces the bounds inferred from this variant's fields. The
+///   bounds of all variants are combined, and each is only added once.
+///
+/// Field attributes:
+/// - `#[debug = "0b{:08b}"]`: formats the field with the given format string instead of `Debug`.
+///   Such fields don't add bounds, since the format decides which trait is needed, and neither do
+///   skipped, redacted or `with` fields.
+/// - `#[debug(skip)]`: leaves the field out.
+/// - `#[debug(redact)]`: prints `<redacted>` instead of the value, `#[debug(redact = "len")]`
+///   prints `<redacted, len 12>` using the field's `len()`.
+/// - `#[debug(with = "path::to::fmt_fn")]`: formats the field with a
+///   `fn(&T, &mut fmt::Formatter) -> fmt::Result`, deref coercion applies to its argument.
+/// - `#[debug(max_items = N)]`: prints only the first `N` items of a collection, followed by
+///   `.. 12 more`. Maps and sets are recognized by the name of their type.
+/// - `#[debug(rename = "id")]`: prints the field under another name.
+/// - `#[debug(flatten)]`: prints the fields of the field's type in its place, as if they were
+///   fields of this struct. The type has to derive `CustomDebug` and have named fields.
+/// - `#[debug(bound = "...")]`: replaces the bounds inferred from this field's type.
+#[proc_macro_derive(CustomDebug, attributes(debug))]
+pub fn derive_debug(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand::derive(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives `Display` from a format string given with `#[display("...")]`.
+///
+/// Placeholders name the fields they print, `{name}` or `{0}` for tuple fields, and take any
+/// format spec: `#[display("{name} ({id:#x})")]`. Widths and precisions can come from fields too,
+/// as in `{value:width$}`. Fields that don't exist are an error.
+///
+/// On enums every variant takes its own template. One on the enum is used for the variants
+/// without, and unit variants without any print their name.
+///
+/// Type parameters are bounded like for `CustomDebug`, by the traits their placeholders use.
+#[proc_macro_derive(CustomDisplay, attributes(display))]
+pub fn derive_display(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    display::derive(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
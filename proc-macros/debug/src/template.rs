@@ -0,0 +1,146 @@
+//! Parsing of `#[display("...")]` format strings.
+//!
+//! The string is handed to `write!` in the end, so the parser only has to find out which fields
+//! it refers to and which formatting trait each of them needs. Every field reference is renamed
+//! to the variable the field is bound to.
+
+use std::fmt::Write;
+
+/// How a placeholder refers to a field: `{name}`, `{0}` or the implicit `{}`.
+#[derive(Clone, PartialEq)]
+pub enum Arg {
+    Named(String),
+    Index(usize),
+}
+
+/// A field used by the template, with how it's used.
+pub struct Use {
+    pub arg: Arg,
+    /// The formatting trait, e.g. `Display` or `LowerHex`, `None` if the field is a width or
+    /// precision given as `name$`.
+    pub trait_name: Option<&'static str>,
+}
+
+/// The template after renaming, and every field it uses.
+pub struct Template {
+    pub format: String,
+    pub uses: Vec<Use>,
+}
+
+/// Parses `template`, renaming each field reference with `rename`, which is also told the trait
+/// the field is formatted with, `None` if it's used as a width or precision.
+pub fn parse(
+    template: &str,
+    mut rename: impl FnMut(&Arg, Option<&'static str>) -> Result<String, String>,
+) -> Result<Template, String> {
+    let mut format = String::new();
+    let mut uses = Vec::new();
+    let mut next_index = 0;
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                format.push_str("{{");
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                format.push_str("}}");
+            }
+            '}' => return Err("unmatched `}` in format string, use `}}` to print a brace".to_owned()),
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err("unclosed `{` in format string, use `{{` to print a brace".to_owned()),
+                    }
+                }
+                let (arg, spec) = match placeholder.split_once(':') {
+                    Some((arg, spec)) => (arg.trim(), spec),
+                    None => (placeholder.trim(), ""),
+                };
+
+                let arg = parse_arg(arg, &mut next_index)?;
+                let trait_name = trait_name(spec);
+                write!(format, "{{{}", rename(&arg, Some(trait_name))?).unwrap();
+                uses.push(Use { arg, trait_name: Some(trait_name) });
+                if !spec.is_empty() {
+                    format.push(':');
+                    format.push_str(&rename_counts(spec, &mut uses, &mut rename)?);
+                }
+                format.push('}');
+            }
+            c => format.push(c),
+        }
+    }
+
+    Ok(Template { format, uses })
+}
+
+fn parse_arg(arg: &str, next_index: &mut usize) -> Result<Arg, String> {
+    let is_ident = arg.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && arg.chars().all(|c| c.is_alphanumeric() || c == '_');
+
+    if arg.is_empty() {
+        *next_index += 1;
+        Ok(Arg::Index(*next_index - 1))
+    } else if let Ok(index) = arg.parse() {
+        Ok(Arg::Index(index))
+    } else if is_ident {
+        Ok(Arg::Named(arg.to_owned()))
+    } else {
+        Err(format!("expected a field name or index, found `{}`", arg))
+    }
+}
+
+/// Renames the `name$` and `0$` widths and precisions in a format spec.
+fn rename_counts(
+    spec: &str,
+    uses: &mut Vec<Use>,
+    rename: &mut impl FnMut(&Arg, Option<&'static str>) -> Result<String, String>,
+) -> Result<String, String> {
+    if spec.contains(".*") {
+        return Err("`.*` precisions aren't supported, name the field with `.name$`".to_owned());
+    }
+
+    let mut renamed = String::new();
+    let mut word = String::new();
+    for c in spec.chars() {
+        if c.is_alphanumeric() || c == '_' {
+            word.push(c);
+            continue;
+        }
+        if c == '$' && !word.is_empty() {
+            let arg = parse_arg(&word, &mut 0)?;
+            renamed.push_str(&rename(&arg, None)?);
+            uses.push(Use { arg, trait_name: None });
+        } else {
+            renamed.push_str(&word);
+        }
+        word.clear();
+        renamed.push(c);
+    }
+    renamed.push_str(&word);
+
+    Ok(renamed)
+}
+
+/// The trait a format spec formats with, going by the type at its end.
+fn trait_name(spec: &str) -> &'static str {
+    if spec.ends_with('?') {
+        return "Debug";
+    }
+    match spec.chars().last() {
+        Some('x') => "LowerHex",
+        Some('X') => "UpperHex",
+        Some('o') => "Octal",
+        Some('b') => "Binary",
+        Some('e') => "LowerExp",
+        Some('E') => "UpperExp",
+        Some('p') => "Pointer",
+        _ => "Display",
+    }
+}
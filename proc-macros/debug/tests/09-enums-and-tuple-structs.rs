@@ -0,0 +1,64 @@
+// Tuple structs, unit structs and enums with any kind of variant are formatted
+// exactly like #[derive(Debug)] formats them: `debug_tuple` for tuple fields,
+// `debug_struct` for named fields and just the name for unit structs and unit
+// variants. Enum variants are printed without the enum's name.
+//
+// #[debug = "..."] works on the fields of all of them.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Point(i32, #[debug = "{:.1}"] f64);
+
+#[derive(CustomDebug)]
+pub struct Marker;
+
+#[derive(CustomDebug)]
+pub struct Wrapper<T>(T);
+
+#[derive(CustomDebug)]
+pub enum Error<E> {
+    NotFound,
+    Io(E),
+    Parse {
+        line: usize,
+        #[debug = "{:#x}"]
+        offset: u32,
+    },
+    Empty(),
+    Raw { r#type: &'static str },
+}
+
+#[derive(Debug)]
+pub enum Expected<E> {
+    NotFound,
+    Io(E),
+    Parse { line: usize, offset: &'static str },
+    Empty(),
+    Raw { r#type: &'static str },
+}
+
+#[derive(CustomDebug)]
+pub enum Never {}
+
+fn main() {
+    assert_eq!(format!("{:?}", Point(1, 2.25)), "Point(1, 2.2)");
+    assert_eq!(format!("{:?}", Marker), "Marker");
+    assert_eq!(format!("{:?}", Wrapper("a")), r#"Wrapper("a")"#);
+    assert_eq!(format!("{:#?}", Wrapper(Marker)), "Wrapper(\n    Marker,\n)");
+
+    let pairs = [
+        (Error::NotFound, Expected::NotFound),
+        (Error::Io("disk"), Expected::Io("disk")),
+        (Error::Parse { line: 3, offset: 255 }, Expected::Parse { line: 3, offset: "0xff" }),
+        (Error::Empty(), Expected::Empty()),
+        (Error::Raw { r#type: "t" }, Expected::Raw { r#type: "t" }),
+    ];
+    for (error, expected) in &pairs {
+        let expected = format!("{:?}", expected).replace("\"0xff\"", "0xff");
+        assert_eq!(format!("{:?}", error), expected);
+    }
+
+    let pretty = format!("{:#?}", Error::<()>::Parse { line: 1, offset: 16 });
+    assert_eq!(pretty, "Parse {\n    line: 1,\n    offset: 0x10,\n}");
+}
@@ -0,0 +1,76 @@
+// Fields can be left out with #[debug(skip)], hidden with #[debug(redact)] or
+// formatted by a function with #[debug(with = "path::to::fmt_fn")], which has
+// the signature `fn(&T, &mut fmt::Formatter) -> fmt::Result`.
+//
+// A redacted field prints as `<redacted>`. With #[debug(redact = "len")] the
+// output also includes the field's `len()`, which is often enough to debug an
+// empty or truncated secret without leaking it.
+//
+// None of these need the field's type to implement Debug.
+
+use derive_debug::CustomDebug;
+use std::fmt::{self, Debug};
+
+mod fmt_helpers {
+    use std::fmt;
+
+    pub fn hex(bytes: &[u8], f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in bytes {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct NotDebug;
+
+fn opaque<T>(_: &T, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("..")
+}
+
+#[derive(CustomDebug)]
+pub struct Credentials<T> {
+    user: String,
+    #[debug(redact)]
+    password: String,
+    #[debug(redact = "len")]
+    token: String,
+    #[debug(with = "fmt_helpers::hex")]
+    salt: Vec<u8>,
+    #[debug(skip)]
+    cache: NotDebug,
+    #[debug(with = "opaque")]
+    extra: T,
+}
+
+#[derive(CustomDebug)]
+pub enum Auth {
+    Key(#[debug(redact)] String, u32),
+    Session {
+        #[debug(skip)]
+        handle: NotDebug,
+        id: u64,
+    },
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    let credentials = Credentials {
+        user: "admin".to_owned(),
+        password: "hunter2".to_owned(),
+        token: "abcdef".to_owned(),
+        salt: vec![0xde, 0xad],
+        cache: NotDebug,
+        extra: NotDebug,
+    };
+    assert_eq!(
+        format!("{:?}", credentials),
+        r#"Credentials { user: "admin", password: <redacted>, token: <redacted, len 6>, salt: dead, extra: .. }"#,
+    );
+
+    assert_eq!(format!("{:?}", Auth::Key("secret".to_owned(), 1)), "Key(<redacted>, 1)");
+    assert_eq!(format!("{:?}", Auth::Session { handle: NotDebug, id: 7 }), "Session { id: 7 }");
+
+    assert_debug::<Credentials<NotDebug>>();
+}
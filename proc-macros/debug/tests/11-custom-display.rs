@@ -0,0 +1,82 @@
+// The sibling derive CustomDisplay generates Display from a format string given
+// with #[display("...")]. Placeholders refer to fields by name, or by index for
+// tuple structs, and accept the usual format specs. A field can also supply a
+// width or precision through `name$`.
+//
+// On enums each variant has its own template. A template on the enum itself is
+// used by variants without one, and unit variants fall back to their name.
+//
+// Bounds are inferred like for CustomDebug, but with the trait each placeholder
+// actually uses: `{value}` needs Display, `{value:?}` Debug, `{value:x}`
+// LowerHex and so on. `{value:p}` prints the address the field points to, not
+// the field's own.
+
+use derive_debug::CustomDisplay;
+use std::fmt::Display;
+use std::marker::PhantomData;
+
+#[derive(CustomDisplay)]
+#[display("{name} ({id:#x})")]
+pub struct User {
+    name: String,
+    id: u32,
+}
+
+#[derive(CustomDisplay)]
+#[display("({0}, {1:.1}) {{{}}}")]
+pub struct Point(i32, f64);
+
+#[derive(CustomDisplay)]
+#[display("[{value:>width$}]")]
+pub struct Padded<T> {
+    value: T,
+    width: usize,
+}
+
+#[derive(CustomDisplay)]
+#[display("{0:?}")]
+pub struct Dbg<T, U>(T, PhantomData<U>);
+
+#[derive(CustomDisplay)]
+#[display("request failed")]
+pub enum Error<E> {
+    #[display("io error: {0}")]
+    Io(E),
+    #[display("{line}:{column}: {message}")]
+    Parse { line: usize, column: usize, message: String },
+    Timeout,
+    Other { code: u8 },
+}
+
+#[derive(CustomDisplay)]
+#[display("{shared:p} {boxed:p}")]
+pub struct Handles<'a> {
+    shared: &'a u8,
+    boxed: Box<u8>,
+}
+
+fn assert_display<T: Display>() {}
+
+fn main() {
+    let user = User { name: "ferris".to_owned(), id: 255 };
+    assert_eq!(user.to_string(), "ferris (0xff)");
+
+    assert_eq!(Point(1, 2.25).to_string(), "(1, 2.2) {1}");
+    assert_eq!(Padded { value: "ab", width: 4 }.to_string(), "[  ab]");
+    assert_eq!(Dbg("a", PhantomData::<()>).to_string(), r#""a""#);
+
+    assert_eq!(Error::Io("disk full").to_string(), "io error: disk full");
+    let parse = Error::<&str>::Parse { line: 3, column: 7, message: "expected `;`".to_owned() };
+    assert_eq!(parse.to_string(), "3:7: expected `;`");
+    assert_eq!(Error::<&str>::Timeout.to_string(), "request failed");
+    assert_eq!(Error::<&str>::Other { code: 1 }.to_string(), "request failed");
+
+    let byte = 7;
+    let handles = Handles { shared: &byte, boxed: Box::new(8) };
+    assert_eq!(handles.to_string(), format!("{:p} {:p}", &byte, handles.boxed));
+
+    // U is only used in PhantomData, so it doesn't need Display.
+    struct NotDisplay;
+    assert_display::<Dbg<u8, NotDisplay>>();
+    assert_display::<Padded<&str>>();
+}
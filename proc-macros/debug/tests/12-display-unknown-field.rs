@@ -0,0 +1,22 @@
+// Fields referenced by a #[display("...")] template are checked while expanding
+// the derive, so a typo points at the template instead of producing confusing
+// errors from inside the generated `write!`.
+
+use derive_debug::CustomDisplay;
+
+#[derive(CustomDisplay)]
+#[display("{name} ({idd})")]
+pub struct User {
+    name: String,
+    id: u32,
+}
+
+#[derive(CustomDisplay)]
+pub enum Shape {
+    #[display("circle of radius {0}")]
+    Circle(f64),
+    #[display("{0}x{2}")]
+    Rect(f64, f64),
+}
+
+fn main() {}
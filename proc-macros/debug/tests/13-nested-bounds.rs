@@ -0,0 +1,66 @@
+// Bound inference looks through nested types and only bounds what formatting
+// actually depends on:
+//
+//   - `Vec<Option<T::Value>>` needs `T::Value: Debug`, not `T: Debug`.
+//   - `<T as Trait>::Value` and `dyn Source<U>` are bounded as a whole, since
+//     whether they implement Debug depends on an impl the macro can't see. A
+//     borrowed `&'a dyn Source<U>` is bounded with its lifetime, a bare
+//     `dyn Source<U>` would have to be `'static`.
+//   - Function pointers and raw pointers implement Debug for any parameters and
+//     don't need bounds at all, references need the pointee to be Debug.
+//
+// Bounds already written on the type aren't repeated in the where clause.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+pub trait Trait {
+    type Value;
+}
+
+pub trait Source<T>: Debug {}
+
+#[derive(CustomDebug)]
+pub struct Nested<'a, T: Trait, U, V, W, L: Debug> {
+    values: Vec<Option<T::Value>>,
+    qualified: <T as Trait>::Value,
+    source: Box<dyn Source<U>>,
+    borrowed: &'a dyn Source<U>,
+    callback: fn(V) -> W,
+    raw: *const V,
+    label: &'a L,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+    struct Id;
+
+    impl Trait for Id {
+        type Value = u8;
+    }
+
+    #[derive(Debug)]
+    struct Empty;
+
+    impl Source<NotDebug> for Empty {}
+
+    assert_debug::<Nested<Id, NotDebug, NotDebug, NotDebug, &str>>();
+
+    fn double(value: NotDebug) -> NotDebug {
+        value
+    }
+    let nested = Nested::<Id, NotDebug, NotDebug, NotDebug, &str> {
+        values: vec![Some(1), None],
+        qualified: 2,
+        source: Box::new(Empty),
+        borrowed: &Empty,
+        callback: double,
+        raw: std::ptr::null(),
+        label: &"label",
+    };
+    let debug = format!("{:?}", nested);
+    assert!(debug.starts_with("Nested { values: [Some(1), None], qualified: 2, source: Empty, borrowed: Empty, callback: 0x"));
+    assert!(debug.ends_with(r#", raw: 0x0, label: "label" }"#));
+}
@@ -0,0 +1,24 @@
+// The bounds a type produced by a macro needs can't be inferred, since the
+// derive only sees the macro call. Instead of guessing, the derive asks for the
+// bounds to be given with #[debug(bound = "...")].
+
+use derive_debug::CustomDebug;
+
+macro_rules! list {
+    ($t:ty) => {
+        Vec<$t>
+    };
+}
+
+#[derive(CustomDebug)]
+pub struct Items<T> {
+    items: list!(T),
+}
+
+#[derive(CustomDebug)]
+pub struct Annotated<T> {
+    #[debug(bound = "T: std::fmt::Debug")]
+    items: list!(T),
+}
+
+fn main() {}
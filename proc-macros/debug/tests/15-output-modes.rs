@@ -0,0 +1,81 @@
+// Large or deeply nested values make Debug output hard to read. Three options
+// keep it short:
+//
+// #[debug(compact)] on the type prints it on a single line, even when it is
+// formatted with {:#?} as part of a larger pretty-printed value.
+//
+// #[debug(max_items = N)] on a collection field prints only its first N items
+// followed by how many were left out, as in `[0, 1, .. 9998 more]`. HashMap,
+// BTreeMap, HashSet and BTreeSet fields print with braces like they normally
+// would.
+//
+// #[debug(non_exhaustive)] on the type ends the output with `..` whenever a
+// field was skipped, so a reader can tell that something is missing.
+
+use derive_debug::CustomDebug;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(CustomDebug)]
+#[debug(compact)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(CustomDebug)]
+pub struct Shape {
+    name: &'static str,
+    origin: Point,
+}
+
+#[derive(CustomDebug)]
+pub struct Samples {
+    #[debug(max_items = 2)]
+    values: Vec<u32>,
+    #[debug(max_items = 1)]
+    names: BTreeMap<&'static str, u8>,
+    #[debug(max_items = 5)]
+    tags: BTreeSet<char>,
+}
+
+#[derive(CustomDebug)]
+#[debug(non_exhaustive)]
+pub struct Session {
+    user: &'static str,
+    #[debug(skip)]
+    token: String,
+}
+
+#[derive(CustomDebug)]
+#[debug(non_exhaustive)]
+pub enum Event {
+    Login(&'static str, #[debug(skip)] String),
+    Logout { user: &'static str },
+}
+
+fn main() {
+    let shape = Shape { name: "dot", origin: Point { x: 1, y: -2 } };
+    assert_eq!(
+        format!("{:#?}", shape),
+        "Shape {\n    name: \"dot\",\n    origin: Point { x: 1, y: -2 },\n}",
+    );
+
+    let samples = Samples {
+        values: (0..10000).collect(),
+        names: BTreeMap::from([("a", 1), ("b", 2), ("c", 3)]),
+        tags: BTreeSet::from(['x', 'y']),
+    };
+    assert_eq!(
+        format!("{:?}", samples),
+        "Samples { values: [0, 1, .. 9998 more], names: {\"a\": 1, .. 2 more}, tags: {'x', 'y'} }",
+    );
+
+    let session = Session { user: "ferris", token: String::from("hunter2") };
+    assert_eq!(format!("{:?}", session), "Session { user: \"ferris\", .. }");
+    let _ = session.token;
+
+    let login = Event::Login("ferris", String::new());
+    assert_eq!(format!("{:?}", login), "Login(\"ferris\", ..)");
+    let logout = Event::Logout { user: "ferris" };
+    assert_eq!(format!("{:?}", logout), "Logout { user: \"ferris\" }");
+}
@@ -0,0 +1,60 @@
+// Generic enums often use each type parameter in only some of their variants,
+// and a variant may need a bound that can't be inferred from its fields. The
+// bound attribute is accepted on variants too:
+//
+//     #[debug(bound = "T::Payload: Debug")]
+//
+// which replaces whatever would have been inferred from that variant's fields,
+// while the other variants keep their inferred bounds. The bounds of all
+// variants end up in the same where clause, and a bound that several variants
+// need is only added once.
+
+use derive_debug::CustomDebug;
+use std::fmt::{self, Debug};
+
+pub trait Source {
+    type Payload;
+}
+
+// Only formattable if the payload is, whatever `T` itself is.
+pub struct Envelope<T: Source> {
+    payload: T::Payload,
+}
+
+impl<T: Source> Debug for Envelope<T>
+where
+    T::Payload: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Envelope").field(&self.payload).finish()
+    }
+}
+
+#[derive(CustomDebug)]
+pub enum Event<T: Source, E> {
+    #[debug(bound = "T::Payload: Debug")]
+    Received(Envelope<T>),
+    Failed(E),
+    #[debug(bound = "E: Debug")]
+    Retried { attempt: u32, error: E },
+    Closed,
+}
+
+struct Socket;
+
+impl Source for Socket {
+    type Payload = Vec<u8>;
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    // Socket isn't Debug, only its payload is.
+    assert_debug::<Event<Socket, String>>();
+
+    let event: Event<Socket, String> = Event::Received(Envelope { payload: vec![1, 2] });
+    assert_eq!(format!("{:?}", event), "Received(Envelope([1, 2]))");
+
+    let event: Event<Socket, &str> = Event::Retried { attempt: 2, error: "timeout" };
+    assert_eq!(format!("{:?}", event), "Retried { attempt: 2, error: \"timeout\" }");
+}
@@ -0,0 +1,57 @@
+// Wrapper types around inner configs print as nested records by default,
+// which is hard to scrape from logs. Two options make the output read as one
+// flat record:
+//
+// #[debug(rename = "id")] prints a field under another name.
+//
+// #[debug(flatten)] prints the fields of the field's type in place of the
+// field itself, as if they were declared in the outer struct. This works for
+// any struct with named fields that derives CustomDebug, including generic
+// ones, and for fields of enum variants with named fields.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Limits<T> {
+    max_connections: T,
+    #[debug(rename = "timeout_ms")]
+    timeout: u64,
+    #[debug(skip)]
+    _internal: (),
+}
+
+#[derive(CustomDebug)]
+pub struct Server {
+    #[debug(rename = "id")]
+    server_id: u32,
+    #[debug(flatten)]
+    limits: Limits<u16>,
+    r#type: &'static str,
+}
+
+#[derive(CustomDebug)]
+pub enum Node {
+    Leaf {
+        name: &'static str,
+        #[debug(flatten)]
+        limits: Limits<u8>,
+    },
+}
+
+fn main() {
+    let limits = Limits { max_connections: 10u16, timeout: 500, _internal: () };
+    assert_eq!(format!("{:?}", limits), "Limits { max_connections: 10, timeout_ms: 500 }");
+
+    let server = Server { server_id: 7, limits, r#type: "http" };
+    assert_eq!(
+        format!("{:?}", server),
+        "Server { id: 7, max_connections: 10, timeout_ms: 500, type: \"http\" }",
+    );
+    assert_eq!(
+        format!("{:#?}", server),
+        "Server {\n    id: 7,\n    max_connections: 10,\n    timeout_ms: 500,\n    type: \"http\",\n}",
+    );
+
+    let node = Node::Leaf { name: "edge", limits: Limits { max_connections: 1, timeout: 5, _internal: () } };
+    assert_eq!(format!("{:?}", node), "Leaf { name: \"edge\", max_connections: 1, timeout_ms: 5 }");
+}
@@ -0,0 +1,67 @@
+// Structured loggers want key-value pairs rather than one preformatted string.
+// With #[debug(fields)] on the type, the derive also generates
+//
+//     fn debug_fields(&self) -> impl Iterator<Item = (&'static str, &dyn Debug)>
+//
+// which lists the same fields the Debug impl prints, with the same names and
+// values: skipped fields are left out, renamed fields use their new name, and
+// redacted or formatted fields are formatted the same way. Tuple fields are
+// named by their index.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+#[derive(CustomDebug)]
+#[debug(fields)]
+pub struct Request<T> {
+    #[debug(rename = "request_id")]
+    id: u32,
+    #[debug = "0x{:04x}"]
+    flags: u16,
+    #[debug(redact = "len")]
+    token: String,
+    #[debug(skip)]
+    _retries: u8,
+    #[debug(max_items = 2)]
+    body: Vec<T>,
+    path: &'static str,
+}
+
+#[derive(CustomDebug)]
+#[debug(fields)]
+pub enum Event {
+    Started(u32, #[debug(redact)] &'static str),
+    Stopped { code: i32 },
+    Idle,
+}
+
+fn pairs<'a>(fields: impl Iterator<Item = (&'static str, &'a dyn Debug)>) -> Vec<(&'static str, String)> {
+    fields.map(|(name, value)| (name, format!("{:?}", value))).collect()
+}
+
+fn main() {
+    let request = Request {
+        id: 1,
+        flags: 0x2a,
+        token: String::from("hunter2"),
+        _retries: 0,
+        body: vec![1, 2, 3],
+        path: "/",
+    };
+    assert_eq!(
+        pairs(request.debug_fields()),
+        [
+            ("request_id", "1".to_owned()),
+            ("flags", "0x002a".to_owned()),
+            ("token", "<redacted, len 7>".to_owned()),
+            ("body", "[1, 2, .. 1 more]".to_owned()),
+            ("path", "\"/\"".to_owned()),
+        ],
+    );
+
+    let started = Event::Started(3, "secret");
+    assert_eq!(pairs(started.debug_fields()), [("0", "3".to_owned()), ("1", "<redacted>".to_owned())]);
+    let stopped = Event::Stopped { code: -1 };
+    assert_eq!(pairs(stopped.debug_fields()), [("code", "-1".to_owned())]);
+    assert_eq!(Event::Idle.debug_fields().count(), 0);
+}
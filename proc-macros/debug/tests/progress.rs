@@ -1,12 +1,22 @@
 #[test]
 fn tests() {
     let t = trybuild::TestCases::new();
-    //t.pass("tests/01-parse.rs");
-    //t.pass("tests/02-impl-debug.rs");
-    //t.pass("tests/03-custom-format.rs");
-    //t.pass("tests/04-type-parameter.rs");
-    //t.pass("tests/05-phantom-data.rs");
-    //t.pass("tests/06-bound-trouble.rs");
-    //t.pass("tests/07-associated-type.rs");
-    //t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/01-parse.rs");
+    t.pass("tests/02-impl-debug.rs");
+    t.pass("tests/03-custom-format.rs");
+    t.pass("tests/04-type-parameter.rs");
+    t.pass("tests/05-phantom-data.rs");
+    t.pass("tests/06-bound-trouble.rs");
+    t.pass("tests/07-associated-type.rs");
+    t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/09-enums-and-tuple-structs.rs");
+    t.pass("tests/10-skip-redact-with.rs");
+    t.pass("tests/11-custom-display.rs");
+    t.compile_fail("tests/12-display-unknown-field.rs");
+    t.pass("tests/13-nested-bounds.rs");
+    t.compile_fail("tests/14-undecidable-bound.rs");
+    t.pass("tests/15-output-modes.rs");
+    t.pass("tests/16-variant-bounds.rs");
+    t.pass("tests/17-rename-flatten.rs");
+    t.pass("tests/18-debug-fields.rs");
 }
@@ -0,0 +1,209 @@
+//! Repeating the body and substituting the loop variables in it.
+
+use proc_macro2::{Delimiter, Group, Ident, Literal, TokenStream, TokenTree};
+use syn::ext::IdentExt;
+
+use crate::header::Seq;
+
+/// The value of every loop variable in one repetition.
+type Bindings<'a> = [(&'a Ident, i128)];
+
+pub fn expand(seq: &Seq) -> syn::Result<TokenStream> {
+    // Every combination of values, the last variable changing fastest.
+    let mut combinations: Vec<Vec<(&Ident, i128)>> = vec![Vec::new()];
+    for (ident, sequence) in &seq.vars {
+        combinations = combinations
+            .iter()
+            .flat_map(|bindings| {
+                sequence.0.iter().map(move |&value| {
+                    let mut bindings = bindings.clone();
+                    bindings.push((ident, value));
+                    bindings
+                })
+            })
+            .collect();
+    }
+
+    let tokens: Vec<TokenTree> = seq.body.clone().into_iter().collect();
+    if has_section(&tokens) {
+        expand_sections(&tokens, &combinations)
+    } else {
+        combinations.iter().map(|bindings| substitute(&tokens, bindings)).collect()
+    }
+}
+
+/// Whether `tokens` contain a `#(...)*` section of this `seq!`.
+fn has_section(tokens: &[TokenTree]) -> bool {
+    (0..tokens.len()).any(|i| match &tokens[i] {
+        _ if section(&tokens[i..]).is_some() => true,
+        TokenTree::Group(group) if !is_nested_seq(&tokens[..i]) => {
+            has_section(&group.stream().into_iter().collect::<Vec<_>>())
+        }
+        _ => false,
+    })
+}
+
+/// Repeats every `#(...)*` section in `tokens` once per combination, keeping the rest as is.
+fn expand_sections(tokens: &[TokenTree], combinations: &[Vec<(&Ident, i128)>]) -> syn::Result<TokenStream> {
+    let mut output = TokenStream::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if let Some(content) = section(&tokens[i..]) {
+            let content: Vec<_> = content.stream().into_iter().collect();
+            for bindings in combinations {
+                output.extend(substitute(&content, bindings)?);
+            }
+            i += 3;
+            continue;
+        }
+        match &tokens[i] {
+            TokenTree::Group(group) if !is_nested_seq(&tokens[..i]) => {
+                let stream: Vec<_> = group.stream().into_iter().collect();
+                output.extend([respan(group, expand_sections(&stream, combinations)?)]);
+            }
+            token => output.extend([token.clone()]),
+        }
+        i += 1;
+    }
+    Ok(output)
+}
+
+/// The content of the `#(...)*` section at the start of `tokens`.
+fn section(tokens: &[TokenTree]) -> Option<&Group> {
+    match tokens {
+        [TokenTree::Punct(pound), TokenTree::Group(group), TokenTree::Punct(star), ..]
+            if pound.as_char() == '#' && group.delimiter() == Delimiter::Parenthesis && star.as_char() == '*' =>
+        {
+            Some(group)
+        }
+        _ => None,
+    }
+}
+
+/// Whether the group following `before` is the input of a nested `seq!`, the sections of which
+/// aren't ours to repeat.
+fn is_nested_seq(before: &[TokenTree]) -> bool {
+    match before {
+        [.., TokenTree::Ident(ident), TokenTree::Punct(bang)] => ident == "seq" && bang.as_char() == '!',
+        _ => false,
+    }
+}
+
+/// Replaces the loop variables in `tokens` by their values, pasting `Ident#N` together.
+fn substitute(tokens: &[TokenTree], bindings: &Bindings) -> syn::Result<TokenStream> {
+    let mut output = TokenStream::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = match &tokens[i] {
+            TokenTree::Ident(ident) => {
+                // `Ident#A#B`: bound variables are pasted while they come first, the rest is left
+                // as `#M` or `#1` for a nested `seq!` to paste.
+                // `r#type#N` pastes to `type0`, which isn't raw anymore.
+                let mut pasted = ident.unraw().to_string();
+                let mut is_pasted = false;
+                let mut rest = TokenStream::new();
+                while let [TokenTree::Punct(pound), segment, ..] = &tokens[i + 1..] {
+                    if pound.as_char() != '#' {
+                        break;
+                    }
+                    let value = match segment {
+                        TokenTree::Ident(segment) => value(segment, bindings),
+                        TokenTree::Literal(literal) if is_index(literal) => None,
+                        _ => break,
+                    };
+                    match value {
+                        Some(value) if value < 0 => {
+                            let message = format!("can't paste the negative value {} into an identifier", value);
+                            return Err(syn::Error::new(ident.span(), message));
+                        }
+                        Some(value) if rest.is_empty() => pasted.push_str(&value.to_string()),
+                        Some(value) => {
+                            let mut literal = Literal::i128_unsuffixed(value);
+                            literal.set_span(segment.span());
+                            rest.extend([TokenTree::Punct(pound.clone()), TokenTree::Literal(literal)]);
+                        }
+                        // A value an outer `seq!` left for us.
+                        None if rest.is_empty() && matches!(segment, TokenTree::Literal(_)) => {
+                            pasted.push_str(&segment.to_string());
+                        }
+                        None => rest.extend([TokenTree::Punct(pound.clone()), segment.clone()]),
+                    }
+                    is_pasted |= rest.is_empty();
+                    i += 2;
+                }
+                let token = match value(ident, bindings) {
+                    Some(value) if !is_pasted => {
+                        let mut literal = Literal::i128_unsuffixed(value);
+                        literal.set_span(ident.span());
+                        TokenTree::Literal(literal)
+                    }
+                    _ if !is_pasted => TokenTree::Ident(ident.clone()),
+                    _ => TokenTree::Ident(Ident::new(&pasted, ident.span())),
+                };
+                output.extend([token]);
+                output.extend(rest);
+                i += 1;
+                continue;
+            }
+            TokenTree::Group(group) => {
+                let stream: Vec<_> = group.stream().into_iter().collect();
+                if is_nested_seq(&tokens[..i]) {
+                    respan(group, substitute_nested(&stream, bindings)?)
+                } else {
+                    respan(group, substitute(&stream, bindings)?)
+                }
+            }
+            token => token.clone(),
+        };
+        output.extend([token]);
+        i += 1;
+    }
+    Ok(output)
+}
+
+/// Substitutes the input of a nested `seq!`, whose own loop variables shadow ours in its body.
+fn substitute_nested(tokens: &[TokenTree], bindings: &Bindings) -> syn::Result<TokenStream> {
+    let Some(k) = tokens.iter().position(|token| matches!(token, TokenTree::Ident(ident) if ident == "in")) else {
+        return substitute(tokens, bindings);
+    };
+    let mut shadowed = Vec::new();
+    for token in &tokens[..k] {
+        match token {
+            TokenTree::Ident(ident) => shadowed.push(ident.clone()),
+            TokenTree::Group(group) => shadowed.extend(group.stream().into_iter().filter_map(|token| match token {
+                TokenTree::Ident(ident) => Some(ident),
+                _ => None,
+            })),
+            _ => {}
+        }
+    }
+    let unshadowed: Vec<_> = bindings.iter().filter(|(var, _)| !shadowed.contains(var)).cloned().collect();
+
+    // The loop variables are kept, the sequences can still use ours.
+    let mut output: TokenStream = tokens[..=k].iter().cloned().collect();
+    match tokens[k + 1..].split_last() {
+        Some((TokenTree::Group(body), sequences)) if body.delimiter() == Delimiter::Brace => {
+            output.extend(substitute(sequences, bindings)?);
+            let stream: Vec<_> = body.stream().into_iter().collect();
+            output.extend([respan(body, substitute(&stream, &unshadowed)?)]);
+        }
+        _ => output.extend(substitute(&tokens[k + 1..], bindings)?),
+    }
+    Ok(output)
+}
+
+/// Whether `literal` is a value pasted by an outer `seq!`, which only has digits.
+fn is_index(literal: &Literal) -> bool {
+    literal.to_string().bytes().all(|byte| byte.is_ascii_digit())
+}
+
+fn value(ident: &Ident, bindings: &Bindings) -> Option<i128> {
+    bindings.iter().find(|(var, _)| *var == ident).map(|&(_, value)| value)
+}
+
+/// A group with the same delimiter and span as `group`, but containing `stream`.
+fn respan(group: &Group, stream: TokenStream) -> TokenTree {
+    let mut new = Group::new(group.delimiter(), stream);
+    new.set_span(group.span());
+    TokenTree::Group(new)
+}
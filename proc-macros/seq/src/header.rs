@@ -0,0 +1,125 @@
+//! Parsing of the `N in 0..4 { ... }` input.
+
+use proc_macro2::TokenStream;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{braced, bracketed, parenthesized, Ident, LitInt, Token};
+
+/// The whole input of `seq!`.
+pub struct Seq {
+    /// Each loop variable with the values it takes.
+    pub vars: Vec<(Ident, Sequence)>,
+    pub body: TokenStream,
+}
+
+/// The values of one loop variable, in order.
+pub struct Sequence(pub Vec<i128>);
+
+impl Parse for Seq {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let vars = if input.peek(syn::token::Paren) {
+            let content;
+            let parens = parenthesized!(content in input);
+            let idents = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+            if idents.is_empty() {
+                return Err(syn::Error::new(parens.span.join(), "expected at least one loop variable"));
+            }
+            input.parse::<Token![in]>()?;
+
+            let content;
+            let parens = parenthesized!(content in input);
+            let sequences = Punctuated::<Sequence, Token![,]>::parse_terminated(&content)?;
+            if sequences.len() != idents.len() {
+                return Err(syn::Error::new(
+                    parens.span.join(),
+                    format!("expected {} ranges, one for each loop variable", idents.len()),
+                ));
+            }
+            idents.into_iter().zip(sequences).collect()
+        } else {
+            let ident: Ident = input.parse()?;
+            input.parse::<Token![in]>()?;
+            vec![(ident, input.parse()?)]
+        };
+        for (i, (ident, _)) in vars.iter().enumerate() {
+            if vars[..i].iter().any(|(other, _)| other == ident) {
+                return Err(syn::Error::new(ident.span(), format!("`{}` is already a loop variable", ident)));
+            }
+        }
+
+        let content;
+        braced!(content in input);
+        let body = content.parse()?;
+
+        Ok(Self { vars, body })
+    }
+}
+
+impl Parse for Sequence {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(syn::token::Bracket) {
+            let content;
+            bracketed!(content in input);
+            let values = Punctuated::<Int, Token![,]>::parse_terminated(&content)?;
+            return Ok(Self(values.into_iter().map(|Int(value)| value).collect()));
+        }
+        if !input.peek(syn::token::Paren) {
+            return Self::parse_range(input);
+        }
+
+        let content;
+        parenthesized!(content in input);
+        let Self(mut values) = content.parse()?;
+        while input.peek(Token![.]) {
+            input.parse::<Token![.]>()?;
+            let method: Ident = input.parse()?;
+            let args;
+            parenthesized!(args in input);
+            if method == "rev" && args.is_empty() {
+                values.reverse();
+            } else if method == "step_by" {
+                let step: LitInt = args.parse()?;
+                match step.base10_parse::<usize>()? {
+                    0 => return Err(syn::Error::new(step.span(), "the step has to be greater than zero")),
+                    step => values = values.into_iter().step_by(step).collect(),
+                }
+            } else {
+                return Err(syn::Error::new(method.span(), "expected `.step_by(n)` or `.rev()`"));
+            }
+        }
+
+        Ok(Self(values))
+    }
+}
+
+impl Sequence {
+    /// `0..4` or `0..=3`.
+    fn parse_range(input: ParseStream) -> syn::Result<Self> {
+        let Int(start) = input.parse()?;
+        let inclusive = if input.peek(Token![..=]) {
+            input.parse::<Token![..=]>()?;
+            true
+        } else {
+            input.parse::<Token![..]>()?;
+            false
+        };
+        let Int(end) = input.parse()?;
+
+        let values = match inclusive {
+            true => (start..=end).collect(),
+            false => (start..end).collect(),
+        };
+        Ok(Self(values))
+    }
+}
+
+/// An integer literal, which may be negative.
+struct Int(i128);
+
+impl Parse for Int {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let negative = input.parse::<Option<Token![-]>>()?.is_some();
+        let value = input.parse::<LitInt>()?.base10_parse::<i128>()?;
+        Ok(Self(if negative { -value } else { value }))
+    }
+}
@@ -1,8 +1,32 @@
 use proc_macro::TokenStream;
+use syn::parse_macro_input;
 
+mod body;
+mod header;
+
+/// Repeats its body once for every value of a sequence, replacing the loop variable with the
+/// value as an integer literal.
+///
+/// ```ignore
+/// seq!(N in 0..4 {
+///     fn f#N() -> u64 { N * 2 }
+/// });
+/// ```
+///
+/// - `Ident#N` is pasted together into a single identifier, `f0` to `f3` above. Several loop
+///   variables can be pasted in a row, as in `f#A#B`.
+/// - If the body contains `#(...)*` sections, only those are repeated and the rest of the body
+///   is kept once.
+/// - Sequences are ranges like `0..4`, `-2..=2`, `(0..64).step_by(8)` or `(0..8).rev()`, or
+///   lists like `[1, 2, 4, 8]`. Negative values can't be pasted into identifiers.
+/// - With several loop variables, as in `(A, B) in (0..3, 0..2)`, the body is repeated for
+///   every combination, the last variable changing fastest.
+/// - A `seq!` nested in the body is left for the compiler to expand after the outer one, so its
+///   header can use the outer loop variable, as in `seq!(M in 0..N { ... })`. Its `#(...)*`
+///   sections belong to it, not to the outer `seq!`, and its loop variables shadow outer ones
+///   of the same name in its body.
 #[proc_macro]
 pub fn seq(input: TokenStream) -> TokenStream {
-    let _ = input;
-
-    unimplemented!()
+    let seq = parse_macro_input!(input as header::Seq);
+    body::expand(&seq).unwrap_or_else(syn::Error::into_compile_error).into()
 }
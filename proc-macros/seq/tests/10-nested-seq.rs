@@ -0,0 +1,74 @@
+// A seq! in the body of another one is expanded after the outer one has
+// substituted its loop variable, so the inner range can depend on it:
+//
+//     seq!(N in 1..4 {
+//         seq!(M in 0..N { ... });
+//     });
+//
+// expands to three inner invocations with the ranges 0..1, 0..2 and 0..3.
+//
+// A #(...)* section in the inner body belongs to the inner seq!, so the outer
+// one repeats its whole body here. Loop variables of both can be pasted into
+// one identifier in any order, like `f#N#M`: the outer seq! pastes `f#N` into
+// `f3` and leaves `#M` for the inner one. For `h#M#N` it leaves `h#M#3`, which
+// the inner seq! pastes into `h03`.
+//
+// An inner seq! may reuse the name of an outer loop variable, which then
+// refers to the inner one in its body, like a shadowed variable would. The
+// inner header is still expanded by the outer seq!, so its range can use the
+// outer value.
+
+use seq::seq;
+
+seq!(N in 1..4 {
+    // The sum of 0..N, written out as `0 + 1 + ... + (N - 1) + 0`.
+    const SUM_#N: u32 = seq!(M in 0..N { #(M +)* 0 });
+});
+
+seq!(N in 0..2 {
+    seq!(M in 0..2 {
+        fn cell#N#M() -> (u8, u8) {
+            (N, M)
+        }
+    });
+});
+
+seq!(N in 1..3 {
+    seq!(M in 0..2 {
+        fn h#M#N() -> (u8, u8) {
+            (M, N)
+        }
+    });
+});
+
+seq!(N in 0..2 {
+    mod m#N {
+        use seq::seq;
+
+        seq!(N in 0..2 {
+            pub fn g#N() -> u8 {
+                N
+            }
+        });
+    }
+
+    // The sum of 0..N again, with the inner variable shadowing the outer one.
+    const SHADOWED_#N: u32 = seq!(N in 0..=N { 0 #(+ N)* });
+});
+
+fn main() {
+    assert_eq!(SUM_1, 0);
+    assert_eq!(SUM_2, 1);
+    assert_eq!(SUM_3, 3);
+
+    assert_eq!(cell00(), (0, 0));
+    assert_eq!(cell01(), (0, 1));
+    assert_eq!(cell10(), (1, 0));
+    assert_eq!(cell11(), (1, 1));
+
+    assert_eq!((h01(), h11(), h02(), h12()), ((0, 1), (1, 1), (0, 2), (1, 2)));
+
+    assert_eq!((m0::g0(), m0::g1(), m1::g0(), m1::g1()), (0, 1, 0, 1));
+    assert_eq!(SHADOWED_0, 0);
+    assert_eq!(SHADOWED_1, 1);
+}
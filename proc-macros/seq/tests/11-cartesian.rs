@@ -0,0 +1,39 @@
+// Several loop variables can be given as a tuple, each with its own range:
+//
+//     seq!((A, B) in (0..3, 0..2) { ... });
+//
+// The body, or each #(...)* section of it, is repeated for every combination
+// of values, with the last variable changing fastest: (0, 0), (0, 1), (1, 0),
+// and so on. This is handy for generating matrix-like impls without chaining
+// several macros.
+
+use seq::seq;
+
+pub struct Matrix<const R: usize, const C: usize>;
+
+pub trait Cells {
+    const CELLS: usize;
+}
+
+seq!((R, C) in (1..3, 1..4) {
+    impl Cells for Matrix<R, C> {
+        const CELLS: usize = R * C;
+    }
+});
+
+seq!((A, B) in (0..3, 0..2) {
+    const PAIRS: [(u8, u8); 6] = [#((A, B),)*];
+
+    #[derive(Debug, PartialEq)]
+    enum Cell {
+        #(Cell#A#B,)*
+    }
+});
+
+fn main() {
+    assert_eq!(<Matrix<2, 3>>::CELLS, 6);
+    assert_eq!(<Matrix<1, 1>>::CELLS, 1);
+
+    assert_eq!(PAIRS, [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0), (2, 1)]);
+    assert_eq!(Cell::Cell21 as u8, 5);
+}
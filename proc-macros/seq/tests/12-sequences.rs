@@ -0,0 +1,55 @@
+// Besides MIN..MAX and MIN..=MAX, the header accepts a few other sequences:
+//
+//   - (0..64).step_by(8) for every 8th value: 0, 8, ..., 56
+//   - (0..8).rev() for the values in reverse: 7, 6, ..., 0
+//   - negative bounds like -2..=2
+//   - explicit lists like [1, 2, 4, 8, 16]
+//
+// `step_by` and `rev` can be chained like on an iterator, and each applies to
+// the sequence before it. Any of these can also be used for the variables of
+// the tuple form. Negative values work anywhere an integer literal does, but
+// can't be pasted into an identifier.
+//
+// Raw identifiers like `r#match` are kept as they are, and pasting into one
+// drops the `r#`: `r#type#N` becomes `type0`.
+
+use seq::seq;
+
+pub trait Lanes {
+    const LANES: usize;
+}
+
+// Only power-of-two widths get an impl.
+seq!(N in [1, 2, 4, 8, 16] {
+    pub struct Simd#N;
+
+    impl Lanes for Simd#N {
+        const LANES: usize = N;
+    }
+});
+
+const STEPS: [u32; 8] = seq!(N in (0..64).step_by(8) { [#(N,)*] });
+const COUNTDOWN: [u32; 8] = seq!(N in (0..8).rev() { [#(N,)*] });
+const CHAINED: [u32; 5] = seq!(N in (0..=16).step_by(4).rev() { [#(N,)*] });
+const SIGNED: [i8; 5] = seq!(N in -2..=2 { [#(N,)*] });
+const NEGATIVE_LIST: [i32; 3] = seq!(N in [-1, 0, -100] { [#(N,)*] });
+const PAIRS: [(u8, i8); 4] = seq!((A, B) in ((0..4).step_by(2), [-1, 1]) { [#((A, B),)*] });
+
+seq!(N in [0, 2] {
+    fn r#type#N() -> i32 {
+        let r#match = N;
+        r#match
+    }
+});
+
+fn main() {
+    assert_eq!(Simd1::LANES + Simd2::LANES + Simd4::LANES + Simd8::LANES + Simd16::LANES, 31);
+
+    assert_eq!(STEPS, [0, 8, 16, 24, 32, 40, 48, 56]);
+    assert_eq!(COUNTDOWN, [7, 6, 5, 4, 3, 2, 1, 0]);
+    assert_eq!(CHAINED, [16, 12, 8, 4, 0]);
+    assert_eq!(SIGNED, [-2, -1, 0, 1, 2]);
+    assert_eq!(NEGATIVE_LIST, [-1, 0, -100]);
+    assert_eq!(PAIRS, [(0, -1), (0, 1), (2, -1), (2, 1)]);
+    assert_eq!((type0(), type2()), (0, 2));
+}
@@ -0,0 +1,16 @@
+// Sequences that don't make sense are reported at the part of the header that
+// is wrong: a step of zero, a method other than `step_by` and `rev`, and
+// pasting a negative value into an identifier, which would not be a valid
+// identifier anymore.
+
+use seq::seq;
+
+seq!(N in (0..8).step_by(0) {});
+
+seq!(N in (0..8).skip(2) {});
+
+seq!(N in -1..1 {
+    fn f#N() {}
+});
+
+fn main() {}